@@ -5,4 +5,14 @@ fn main() {
         .file("src/gfx/image/stb_image.c")
         .compile("stb_image");
     println!("cargo:rustc-link-lib=static=stb_image");
+
+    cc::Build::new()
+        .file("src/gfx/image/stb_image_write.c")
+        .compile("stb_image_write");
+    println!("cargo:rustc-link-lib=static=stb_image_write");
+
+    cc::Build::new()
+        .file("src/gfx/truetype/stb_truetype.c")
+        .compile("stb_truetype");
+    println!("cargo:rustc-link-lib=static=stb_truetype");
 }
@@ -0,0 +1,255 @@
+mod state;
+
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use std::f32::consts::PI;
+
+use gfx::{Camera, Graphics, Sprite, TextureHandle};
+use math::{lerp, Vec2};
+use sim::{Input, Phase, World, MAX_FALL_SPEED, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+use self::state::{State, Title, Transition};
+
+/// Nose up angle right after a flap
+const BIRD_MAX_TILT: f32 = PI / 7.0;
+/// Nose down angle when falling at full speed
+const BIRD_MIN_TILT: f32 = -PI / 2.0;
+
+/// Screen shake when the bird crashes
+const SHAKE_TICKS: u32 = 20;
+const SHAKE_AMPLITUDE: f32 = 6.0;
+
+pub struct Textures {
+    background: TextureHandle,
+    ground: TextureHandle,
+    pipe: TextureHandle,
+    bird: [TextureHandle; 3],
+    digits: Vec<TextureHandle>,
+    title: TextureHandle,
+    get_ready: TextureHandle,
+    tap: TextureHandle,
+    paused: TextureHandle,
+    game_over: TextureHandle,
+    panel: TextureHandle,
+    new_best: TextureHandle,
+}
+
+impl Textures {
+    fn load(gfx: &mut Graphics) -> io::Result<Textures> {
+        let mut digits = Vec::with_capacity(10);
+        for i in 0..10 {
+            digits.push(gfx.load_texture(format!("assets/sprites/{}.png", i))?);
+        }
+
+        Ok(Textures {
+            background: gfx.load_texture("assets/sprites/background_day.png")?,
+            ground: gfx.load_texture("assets/sprites/base.png")?,
+            pipe: gfx.load_texture("assets/sprites/pipe_green.png")?,
+            bird: [
+                gfx.load_texture("assets/sprites/yellowbird_upflap.png")?,
+                gfx.load_texture("assets/sprites/yellowbird_midflap.png")?,
+                gfx.load_texture("assets/sprites/yellowbird_downflap.png")?,
+            ],
+            digits,
+            title: gfx.load_texture("assets/sprites/title.png")?,
+            get_ready: gfx.load_texture("assets/sprites/get_ready.png")?,
+            tap: gfx.load_texture("assets/sprites/tap.png")?,
+            paused: gfx.load_texture("assets/sprites/paused.png")?,
+            game_over: gfx.load_texture("assets/sprites/game_over.png")?,
+            panel: gfx.load_texture("assets/sprites/panel.png")?,
+            new_best: gfx.load_texture("assets/sprites/new_best.png")?,
+        })
+    }
+}
+
+/// Data every state has access to
+pub struct Shared {
+    textures: Textures,
+    world: World,
+    best_score: u32,
+    shake: Shake,
+}
+
+/// Decaying camera shake, advanced once per tick
+pub struct Shake {
+    ticks_left: u32,
+}
+
+impl Shake {
+    fn start(&mut self) {
+        self.ticks_left = SHAKE_TICKS;
+    }
+
+    fn update(&mut self) {
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+    }
+
+    /// Camera offset in pixels. Deterministic so replays look the same.
+    fn offset(&self) -> Vec2 {
+        if self.ticks_left == 0 {
+            return Vec2::zero();
+        }
+
+        let t = self.ticks_left as f32;
+        let amplitude = SHAKE_AMPLITUDE * t / SHAKE_TICKS as f32;
+        Vec2::new(
+            ((t * 2.9).sin() * amplitude).round(),
+            ((t * 2.1).cos() * amplitude).round(),
+        )
+    }
+}
+
+pub struct Game {
+    shared: Shared,
+    state: Box<dyn State>,
+    input: Input,
+    is_quit: bool,
+}
+
+impl Game {
+    /// Creates a game whose simulation advances `dt` seconds per `update`.
+    pub fn new(gfx: &mut Graphics, dt: f32) -> io::Result<Game> {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+
+        Ok(Game {
+            shared: Shared {
+                textures: Textures::load(gfx)?,
+                world: World::new(seed, dt),
+                best_score: 0,
+                shake: Shake { ticks_left: 0 },
+            },
+            state: Box::new(Title),
+            input: Input::default(),
+            is_quit: false,
+        })
+    }
+
+    pub fn flap(&mut self) {
+        self.input.flap = true;
+    }
+
+    /// Goes back to the previous screen, pauses, or quits depending on the current state.
+    pub fn back(&mut self) {
+        let transition = self.state.back(&mut self.shared);
+        self.apply(transition);
+    }
+
+    pub fn is_quit(&self) -> bool {
+        self.is_quit
+    }
+
+    pub fn score(&self) -> u32 {
+        self.shared.world.score()
+    }
+
+    pub fn best_score(&self) -> u32 {
+        self.shared.best_score
+    }
+
+    /// Advances the current state by one tick with the input collected since the last one.
+    pub fn update(&mut self) {
+        let input = self.input;
+        self.input = Input::default();
+
+        self.shared.shake.update();
+        let transition = self.state.update(&mut self.shared, input);
+        self.apply(transition);
+    }
+
+    /// Draws the state `alpha` of the way from the previous tick to the current one.
+    pub fn draw(&self, gfx: &mut Graphics, alpha: f32) {
+        let offset = self.shared.shake.offset();
+        gfx.set_camera(&Camera::new(SCREEN_WIDTH, SCREEN_HEIGHT).with_offset(offset.x, offset.y));
+
+        self.state.draw(&self.shared, gfx, alpha);
+    }
+
+    fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::Stay => (),
+            Transition::Switch(state) => self.state = state,
+            Transition::Quit => self.is_quit = true,
+        }
+    }
+}
+
+/// Draws background, pipes, ground and bird.
+fn draw_world(shared: &Shared, gfx: &mut Graphics, alpha: f32) {
+    let textures = &shared.textures;
+    let world = &shared.world;
+
+    draw_sprite(gfx, textures.background, 0.0, 0.0);
+
+    for pipe in world.pipes() {
+        let x = lerp(pipe.prev_x, pipe.x, alpha).round();
+        let sprite = Sprite::for_texture(gfx.texture(textures.pipe)).with_anchor(0.0, 1.0);
+        gfx.draw_sprite(&textures.pipe, &sprite.at(x, pipe.gap_y));
+        // Upper pipe is the same sprite flipped vertically
+        gfx.draw_sprite(&textures.pipe, &sprite.at(x, pipe.gap_top()).flip_y());
+    }
+
+    let ground_width = gfx.texture(textures.ground).width() as f32;
+    let ground_offset = lerp(world.prev_ground_offset(), world.ground_offset(), alpha);
+    let mut x = -(ground_offset % ground_width).round();
+    while x < SCREEN_WIDTH {
+        draw_sprite(gfx, textures.ground, x, 0.0);
+        x += ground_width;
+    }
+
+    let bird = world.bird();
+    let bird_texture = textures.bird[bird.frame()];
+    let bird_y = lerp(bird.prev_y, bird.y, alpha);
+    // Tilt up while rising and dive towards the ground while falling
+    let tilt = match world.phase() {
+        Phase::Ready => 0.0,
+        Phase::Dead => BIRD_MIN_TILT,
+        Phase::Playing | Phase::Falling if bird.vy >= 0.0 => BIRD_MAX_TILT,
+        Phase::Playing | Phase::Falling => {
+            lerp(BIRD_MAX_TILT, BIRD_MIN_TILT, -bird.vy / MAX_FALL_SPEED)
+        }
+    };
+    let bird_sprite = Sprite::for_texture(gfx.texture(bird_texture))
+        .at(bird.x.round(), bird_y.round())
+        .with_anchor(0.5, 0.5)
+        .with_rotation(tilt);
+    gfx.draw_sprite(&bird_texture, &bird_sprite);
+}
+
+/// Draws `number` horizontally centered with its vertical center at `y`.
+fn draw_number(gfx: &mut Graphics, textures: &Textures, number: u32, y: f32) {
+    let digits = number
+        .to_string()
+        .bytes()
+        .map(|b| textures.digits[(b - b'0') as usize])
+        .collect::<Vec<_>>();
+
+    let total_width = digits.iter().map(|&d| texture_size(gfx, d).0).sum::<f32>();
+    let mut x = ((SCREEN_WIDTH - total_width) / 2.0).round();
+    for digit in digits {
+        let (w, h) = texture_size(gfx, digit);
+        draw_sprite(gfx, digit, x, (y - h / 2.0).round());
+        x += w;
+    }
+}
+
+/// Draws `texture` at its natural size with its bottom left corner at `(x, y)`.
+fn draw_sprite(gfx: &mut Graphics, texture: TextureHandle, x: f32, y: f32) {
+    let sprite = Sprite::for_texture(gfx.texture(texture)).at(x, y);
+    gfx.draw_sprite(&texture, &sprite);
+}
+
+/// Draws `texture` horizontally centered with its vertical center at `y`.
+fn draw_sprite_centered(gfx: &mut Graphics, texture: TextureHandle, y: f32) {
+    let (w, h) = texture_size(gfx, texture);
+    let x = ((SCREEN_WIDTH - w) / 2.0).round();
+    draw_sprite(gfx, texture, x, (y - h / 2.0).round());
+}
+
+fn texture_size(gfx: &Graphics, texture: TextureHandle) -> (f32, f32) {
+    let texture = gfx.texture(texture);
+    (texture.width() as f32, texture.height() as f32)
+}
@@ -0,0 +1,171 @@
+use gfx::Graphics;
+use sim::{Event, Input, Phase, SCREEN_HEIGHT};
+
+use super::{draw_number, draw_sprite_centered, draw_world, Shared};
+
+const SCORE_Y: f32 = SCREEN_HEIGHT - 100.0;
+
+pub enum Transition {
+    Stay,
+    Switch(Box<dyn State>),
+    Quit,
+}
+
+/// A screen of the game, e.g. the title screen or the game over panel.
+///
+/// `update` runs once per simulation tick with the input collected since the
+/// previous tick, `draw` runs once per rendered frame.
+pub trait State {
+    fn update(&mut self, shared: &mut Shared, input: Input) -> Transition;
+
+    fn draw(&self, shared: &Shared, gfx: &mut Graphics, alpha: f32);
+
+    /// Called when the player presses Escape
+    fn back(&mut self, shared: &mut Shared) -> Transition;
+}
+
+pub struct Title;
+
+impl State for Title {
+    fn update(&mut self, shared: &mut Shared, input: Input) -> Transition {
+        // Keep the bird hovering and the ground scrolling in the background
+        shared.world.step(Input::default());
+
+        if input.flap {
+            shared.world.reset();
+            return Transition::Switch(Box::new(GetReady));
+        }
+
+        Transition::Stay
+    }
+
+    fn draw(&self, shared: &Shared, gfx: &mut Graphics, alpha: f32) {
+        draw_world(shared, gfx, alpha);
+        draw_sprite_centered(gfx, shared.textures.title, SCREEN_HEIGHT * 0.7);
+        draw_sprite_centered(gfx, shared.textures.tap, SCREEN_HEIGHT * 0.35);
+    }
+
+    fn back(&mut self, _shared: &mut Shared) -> Transition {
+        Transition::Quit
+    }
+}
+
+pub struct GetReady;
+
+impl State for GetReady {
+    fn update(&mut self, shared: &mut Shared, input: Input) -> Transition {
+        shared.world.step(input);
+
+        if shared.world.phase() != Phase::Ready {
+            return Transition::Switch(Box::new(Playing));
+        }
+
+        Transition::Stay
+    }
+
+    fn draw(&self, shared: &Shared, gfx: &mut Graphics, alpha: f32) {
+        draw_world(shared, gfx, alpha);
+        draw_number(gfx, &shared.textures, shared.world.score(), SCORE_Y);
+        draw_sprite_centered(gfx, shared.textures.get_ready, SCREEN_HEIGHT * 0.7);
+        draw_sprite_centered(gfx, shared.textures.tap, SCREEN_HEIGHT * 0.35);
+    }
+
+    fn back(&mut self, _shared: &mut Shared) -> Transition {
+        Transition::Switch(Box::new(Title))
+    }
+}
+
+pub struct Playing;
+
+impl State for Playing {
+    fn update(&mut self, shared: &mut Shared, input: Input) -> Transition {
+        for event in shared.world.step(input) {
+            match event {
+                Event::HitPipe | Event::HitGround => shared.shake.start(),
+                Event::Flapped | Event::Scored(_) => (),
+            }
+        }
+
+        if shared.world.phase() == Phase::Dead {
+            let score = shared.world.score();
+            let is_new_best = score > shared.best_score;
+            if is_new_best {
+                shared.best_score = score;
+            }
+            return Transition::Switch(Box::new(GameOver { is_new_best }));
+        }
+
+        Transition::Stay
+    }
+
+    fn draw(&self, shared: &Shared, gfx: &mut Graphics, alpha: f32) {
+        draw_world(shared, gfx, alpha);
+        draw_number(gfx, &shared.textures, shared.world.score(), SCORE_Y);
+    }
+
+    fn back(&mut self, shared: &mut Shared) -> Transition {
+        // Nothing to pause once the bird is falling
+        if shared.world.phase() != Phase::Playing {
+            return Transition::Stay;
+        }
+
+        Transition::Switch(Box::new(Paused))
+    }
+}
+
+pub struct Paused;
+
+impl State for Paused {
+    fn update(&mut self, _shared: &mut Shared, input: Input) -> Transition {
+        if input.flap {
+            return Transition::Switch(Box::new(Playing));
+        }
+
+        Transition::Stay
+    }
+
+    fn draw(&self, shared: &Shared, gfx: &mut Graphics, _alpha: f32) {
+        // The world is frozen, so don't interpolate towards a tick that never comes
+        draw_world(shared, gfx, 1.0);
+        draw_number(gfx, &shared.textures, shared.world.score(), SCORE_Y);
+        draw_sprite_centered(gfx, shared.textures.paused, SCREEN_HEIGHT * 0.6);
+    }
+
+    fn back(&mut self, _shared: &mut Shared) -> Transition {
+        Transition::Switch(Box::new(Playing))
+    }
+}
+
+pub struct GameOver {
+    is_new_best: bool,
+}
+
+impl State for GameOver {
+    fn update(&mut self, shared: &mut Shared, input: Input) -> Transition {
+        if input.flap {
+            shared.world.reset();
+            return Transition::Switch(Box::new(GetReady));
+        }
+
+        Transition::Stay
+    }
+
+    fn draw(&self, shared: &Shared, gfx: &mut Graphics, _alpha: f32) {
+        let textures = &shared.textures;
+
+        draw_world(shared, gfx, 1.0);
+        draw_sprite_centered(gfx, textures.game_over, SCREEN_HEIGHT * 0.75);
+
+        let panel_y = SCREEN_HEIGHT * 0.4;
+        draw_sprite_centered(gfx, textures.panel, panel_y);
+        draw_number(gfx, textures, shared.world.score(), panel_y + 22.0);
+        draw_number(gfx, textures, shared.best_score, panel_y - 22.0);
+        if self.is_new_best {
+            draw_sprite_centered(gfx, textures.new_best, panel_y - 80.0);
+        }
+    }
+
+    fn back(&mut self, _shared: &mut Shared) -> Transition {
+        Transition::Switch(Box::new(Title))
+    }
+}
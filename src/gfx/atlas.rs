@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::io;
+
+use math::Rect;
+
+use super::GlTexture2D;
+
+/// Pixel rectangle inside a texture, `(x, y)` is the top left corner like in
+/// image editors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureRegion {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl TextureRegion {
+    pub fn new(x: u32, y: u32, w: u32, h: u32) -> TextureRegion {
+        TextureRegion { x, y, w, h }
+    }
+
+    /// Whether the region lies within a `w` by `h` texture. Regions may come
+    /// from descriptor files, so sums past `u32::MAX` count as outside.
+    pub fn is_within(&self, w: u32, h: u32) -> bool {
+        let fits = |start: u32, len: u32, max: u32| match start.checked_add(len) {
+            Some(end) => end <= max,
+            None => false,
+        };
+        fits(self.x, self.w, w) && fits(self.y, self.h, h)
+    }
+
+    /// Normalized texture coordinates of this region in `texture`.
+    ///
+    /// Images are flipped when uploaded, so texcoord `v = 0` is the bottom row
+    /// of the image.
+    pub fn texcoords(&self, texture: &GlTexture2D) -> Rect {
+        let tw = texture.width() as f32;
+        let th = texture.height() as f32;
+        Rect {
+            x: self.x as f32 / tw,
+            y: 1.0 - (self.y as f32 + self.h as f32) / th,
+            w: self.w as f32 / tw,
+            h: self.h as f32 / th,
+        }
+    }
+}
+
+/// One texture holding many named sprites.
+pub struct Atlas {
+    texture: GlTexture2D,
+    regions: HashMap<String, TextureRegion>,
+}
+
+impl Atlas {
+    /// Fails if any region lies outside of `texture`.
+    pub fn new(texture: GlTexture2D, regions: HashMap<String, TextureRegion>) -> io::Result<Atlas> {
+        for (name, region) in regions.iter() {
+            if !region.is_within(texture.width(), texture.height()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Atlas region {} {:?} is outside of the {}x{} texture",
+                        name,
+                        region,
+                        texture.width(),
+                        texture.height()
+                    ),
+                ));
+            }
+        }
+
+        Ok(Atlas { texture, regions })
+    }
+
+    /// Parses an atlas descriptor.
+    ///
+    /// Each non-empty line is `name x y w h` in pixels, separated by whitespace.
+    /// Lines starting with `#` are comments.
+    ///
+    ///     # name          x   y   w   h
+    ///     bird_midflap    0   0   34  24
+    ///     pipe_green      34  0   52  320
+    pub fn parse_descriptor(descriptor: &str) -> io::Result<HashMap<String, TextureRegion>> {
+        let mut regions = HashMap::new();
+
+        for (i, line) in descriptor.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Atlas descriptor line {}: {}: {}", i + 1, reason, line),
+                )
+            };
+
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.len() != 5 {
+                return Err(invalid("expected `name x y w h`"));
+            }
+
+            let mut values = [0u32; 4];
+            for (value, field) in values.iter_mut().zip(fields[1..].iter()) {
+                *value = field.parse().map_err(|_| invalid("invalid number"))?;
+            }
+
+            let region = TextureRegion::new(values[0], values[1], values[2], values[3]);
+            if regions.insert(fields[0].to_string(), region).is_some() {
+                return Err(invalid("duplicated name"));
+            }
+        }
+
+        Ok(regions)
+    }
+
+    pub fn texture(&self) -> &GlTexture2D {
+        &self.texture
+    }
+
+    pub fn region(&self, name: &str) -> Option<&TextureRegion> {
+        self.regions.get(name)
+    }
+
+    pub fn regions(&self) -> &HashMap<String, TextureRegion> {
+        &self.regions
+    }
+}
+
+struct Shelf {
+    y: u32,
+    h: u32,
+    /// Width used so far
+    x: u32,
+}
+
+/// Packs rectangles into rows ("shelves") as they arrive, e.g. glyphs
+/// rasterized on demand. Works well when the rectangles have similar heights.
+pub struct ShelfPacker {
+    w: u32,
+    h: u32,
+    /// Empty pixels kept to the right and below every rectangle
+    padding: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl ShelfPacker {
+    pub fn new(w: u32, h: u32, padding: u32) -> ShelfPacker {
+        ShelfPacker {
+            w,
+            h,
+            padding,
+            shelves: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.w
+    }
+
+    pub fn height(&self) -> u32 {
+        self.h
+    }
+
+    /// Region for a `w` by `h` rectangle, `None` if there is no room left.
+    pub fn pack(&mut self, w: u32, h: u32) -> Option<TextureRegion> {
+        let padded_w = w + self.padding;
+        let padded_h = h + self.padding;
+        let area_w = self.w;
+
+        // The shortest shelf the rectangle fits on wastes the least space
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.h >= padded_h && shelf.x + padded_w <= area_w)
+            .min_by_key(|shelf| shelf.h);
+        if let Some(shelf) = best {
+            let region = TextureRegion::new(shelf.x, shelf.y, w, h);
+            shelf.x += padded_w;
+            return Some(region);
+        }
+
+        let y = self.shelves.last().map(|s| s.y + s.h).unwrap_or(0);
+        if y + padded_h > self.h || padded_w > self.w {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y,
+            h: padded_h,
+            x: padded_w,
+        });
+        Some(TextureRegion::new(0, y, w, h))
+    }
+
+    /// Enlarges the area. Regions packed so far stay where they are.
+    pub fn grow(&mut self, w: u32, h: u32) {
+        assert!(w >= self.w && h >= self.h);
+        self.w = w;
+        self.h = h;
+    }
+
+    /// Forgets every packed region.
+    pub fn clear(&mut self) {
+        self.shelves.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_within_texture() {
+        assert!(TextureRegion::new(0, 0, 16, 16).is_within(16, 16));
+        assert!(TextureRegion::new(16, 16, 0, 0).is_within(16, 16));
+        assert!(!TextureRegion::new(1, 0, 16, 16).is_within(16, 16));
+        assert!(!TextureRegion::new(0, 1, 16, 16).is_within(16, 16));
+    }
+
+    #[test]
+    fn region_overflowing_u32_is_outside() {
+        assert!(!TextureRegion::new(u32::MAX, 0, 2, 1).is_within(16, 16));
+        assert!(!TextureRegion::new(0, 2, 1, u32::MAX).is_within(16, 16));
+    }
+
+    #[test]
+    fn packs_along_a_shelf_with_padding() {
+        let mut packer = ShelfPacker::new(32, 32, 1);
+        assert_eq!(packer.pack(10, 8), Some(TextureRegion::new(0, 0, 10, 8)));
+        assert_eq!(packer.pack(10, 8), Some(TextureRegion::new(11, 0, 10, 8)));
+        // 22 + 10 fits, but not with the padding to its right
+        assert_eq!(packer.pack(10, 8), Some(TextureRegion::new(0, 9, 10, 8)));
+    }
+
+    #[test]
+    fn prefers_the_shortest_shelf_that_fits() {
+        let mut packer = ShelfPacker::new(64, 64, 0);
+        assert_eq!(packer.pack(40, 20), Some(TextureRegion::new(0, 0, 40, 20)));
+        assert_eq!(packer.pack(40, 10), Some(TextureRegion::new(0, 20, 40, 10)));
+
+        assert_eq!(packer.pack(8, 8), Some(TextureRegion::new(40, 20, 8, 8)));
+        assert_eq!(packer.pack(8, 15), Some(TextureRegion::new(40, 0, 8, 15)));
+        // Taller than every shelf opens a new one
+        assert_eq!(packer.pack(8, 21), Some(TextureRegion::new(0, 30, 8, 21)));
+    }
+
+    #[test]
+    fn fails_when_full() {
+        let mut packer = ShelfPacker::new(16, 16, 1);
+        assert_eq!(packer.pack(16, 4), None);
+        assert_eq!(packer.pack(4, 16), None);
+
+        assert!(packer.pack(15, 7).is_some());
+        assert!(packer.pack(15, 7).is_some());
+        assert_eq!(packer.pack(1, 1), None);
+    }
+
+    #[test]
+    fn grow_keeps_packed_regions() {
+        let mut packer = ShelfPacker::new(16, 16, 0);
+        assert_eq!(packer.pack(16, 10), Some(TextureRegion::new(0, 0, 16, 10)));
+        assert_eq!(packer.pack(8, 8), None);
+
+        packer.grow(32, 32);
+        assert_eq!((packer.width(), packer.height()), (32, 32));
+        // The existing shelf gets wider, new shelves go below it
+        assert_eq!(packer.pack(8, 8), Some(TextureRegion::new(16, 0, 8, 8)));
+        assert_eq!(packer.pack(32, 20), Some(TextureRegion::new(0, 10, 32, 20)));
+    }
+
+    #[test]
+    fn clear_starts_over() {
+        let mut packer = ShelfPacker::new(16, 16, 0);
+        assert!(packer.pack(16, 16).is_some());
+        assert_eq!(packer.pack(1, 1), None);
+
+        packer.clear();
+        assert_eq!(packer.pack(16, 16), Some(TextureRegion::new(0, 0, 16, 16)));
+    }
+}
@@ -0,0 +1,34 @@
+use math::{Trans2, Vec2};
+
+/// Maps logical game pixels to clip space.
+///
+/// The visible area is `width` x `height` pixels with the origin at the bottom
+/// left, moved by `offset`, e.g. to shake the screen.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub width: f32,
+    pub height: f32,
+    pub offset: Vec2,
+}
+
+impl Camera {
+    pub fn new(width: f32, height: f32) -> Camera {
+        Camera {
+            width,
+            height,
+            offset: Vec2::zero(),
+        }
+    }
+
+    pub fn with_offset(mut self, x: f32, y: f32) -> Camera {
+        self.offset = Vec2::new(x, y);
+        self
+    }
+
+    /// Orthographic projection from logical pixels to clip space
+    pub fn projection(&self) -> Trans2 {
+        Trans2::translate(-1.0, -1.0)
+            * Trans2::scale(2.0 / self.width, 2.0 / self.height)
+            * Trans2::translate(-self.offset.x, -self.offset.y)
+    }
+}
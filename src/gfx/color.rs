@@ -0,0 +1,36 @@
+/// RGBA color with straight (not pre-multiplied) alpha, each channel in `[0, 1]`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    pub fn rgb(r: f32, g: f32, b: f32) -> Color {
+        Color::rgba(r, g, b, 1.0)
+    }
+
+    pub fn white() -> Color {
+        Color::rgb(1.0, 1.0, 1.0)
+    }
+
+    pub fn black() -> Color {
+        Color::rgb(0.0, 0.0, 0.0)
+    }
+
+    /// Same color with alpha multiplied by `alpha`, e.g. for fading
+    pub fn with_alpha(&self, alpha: f32) -> Color {
+        Color::rgba(self.r, self.g, self.b, self.a * alpha)
+    }
+
+    /// Components in the pre-multiplied alpha format the blend state expects
+    pub fn premultiplied(&self) -> [f32; 4] {
+        [self.r * self.a, self.g * self.a, self.b * self.a, self.a]
+    }
+}
@@ -0,0 +1,475 @@
+use std;
+use std::collections::HashMap;
+use std::io;
+
+use math::Vec2;
+
+use super::atlas::TextureRegion;
+use super::{Color, GlTexture2D, Sprite};
+
+/// Where a character is in the font texture and how to place it, in texture
+/// pixels like BMFont.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyph {
+    pub region: TextureRegion,
+    /// Offset of the glyph's left edge from the pen position
+    pub offset_x: i32,
+    /// Offset of the glyph's top edge from the top of the line
+    pub offset_y: i32,
+    /// How far the pen moves after this glyph
+    pub advance: i32,
+}
+
+/// Metrics of a font without its texture, see `BitmapFont::parse_descriptor`
+#[derive(Clone, Debug)]
+pub struct FontDescriptor {
+    pub line_height: u32,
+    /// Distance from the top of a line to its baseline
+    pub base: u32,
+    /// Image file of the glyph texture, relative to the descriptor
+    pub page: String,
+    pub glyphs: HashMap<char, Glyph>,
+    /// Extra advance between pairs of characters
+    pub kernings: HashMap<(char, char), i32>,
+}
+
+/// Font whose glyphs are pre-rendered into one texture.
+///
+/// Glyph textures are usually white, often single channel `A8`, so the color
+/// comes from the tint when drawing.
+pub struct BitmapFont {
+    texture: GlTexture2D,
+    descriptor: FontDescriptor,
+}
+
+impl BitmapFont {
+    /// Fails if any glyph lies outside of `texture`.
+    pub fn new(texture: GlTexture2D, descriptor: FontDescriptor) -> io::Result<BitmapFont> {
+        for (c, glyph) in descriptor.glyphs.iter() {
+            if !glyph.region.is_within(texture.width(), texture.height()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Glyph {:?} {:?} is outside of the {}x{} texture",
+                        c,
+                        glyph.region,
+                        texture.width(),
+                        texture.height()
+                    ),
+                ));
+            }
+        }
+
+        Ok(BitmapFont {
+            texture,
+            descriptor,
+        })
+    }
+
+    /// Parses a font descriptor in the BMFont text format with a single page.
+    ///
+    /// Every line is a tag followed by `key=value` pairs. Only the tags and
+    /// keys needed for drawing are read, the rest is ignored.
+    ///
+    ///     common lineHeight=32 base=26 scaleW=256 scaleH=256 pages=1
+    ///     page id=0 file="score.png"
+    ///     char id=48 x=0 y=0 width=16 height=24 xoffset=1 yoffset=4 xadvance=18 page=0 chnl=15
+    ///     kerning first=49 second=49 amount=-2
+    pub fn parse_descriptor(descriptor: &str) -> io::Result<FontDescriptor> {
+        let mut line_height = None;
+        let mut base = None;
+        let mut page = None;
+        let mut glyphs = HashMap::new();
+        let mut kernings = HashMap::new();
+
+        for (i, line) in descriptor.lines().enumerate() {
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Font descriptor line {}: {}: {}", i + 1, reason, line),
+                )
+            };
+
+            let (tag, pairs) = match split_line(line) {
+                Some(split) => split,
+                None => continue,
+            };
+            let value = |key: &str| {
+                pairs
+                    .iter()
+                    .find(|&&(k, _)| k == key)
+                    .map(|(_, v)| v.as_str())
+                    .ok_or_else(|| invalid(&format!("missing `{}`", key)))
+            };
+            let number = |key: &str| {
+                value(key)?
+                    .parse::<i32>()
+                    .map_err(|_| invalid(&format!("invalid number for `{}`", key)))
+            };
+            let unsigned = |key: &str| {
+                let n = number(key)?;
+                if n < 0 {
+                    return Err(invalid(&format!("negative `{}`", key)));
+                }
+                Ok(n as u32)
+            };
+            let character = |key: &str| {
+                std::char::from_u32(unsigned(key)?)
+                    .ok_or_else(|| invalid(&format!("invalid character for `{}`", key)))
+            };
+
+            match tag {
+                "common" => {
+                    line_height = Some(unsigned("lineHeight")?);
+                    base = Some(unsigned("base")?);
+                    if unsigned("pages")? != 1 {
+                        return Err(invalid("only fonts with one page are supported"));
+                    }
+                }
+                "page" => page = Some(value("file")?.to_string()),
+                "char" => {
+                    let glyph = Glyph {
+                        region: TextureRegion::new(
+                            unsigned("x")?,
+                            unsigned("y")?,
+                            unsigned("width")?,
+                            unsigned("height")?,
+                        ),
+                        offset_x: number("xoffset")?,
+                        offset_y: number("yoffset")?,
+                        advance: number("xadvance")?,
+                    };
+                    glyphs.insert(character("id")?, glyph);
+                }
+                "kerning" => {
+                    kernings.insert(
+                        (character("first")?, character("second")?),
+                        number("amount")?,
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let missing = |what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Font descriptor has no {}", what),
+            )
+        };
+
+        Ok(FontDescriptor {
+            line_height: line_height.ok_or_else(|| missing("`common` line"))?,
+            base: base.ok_or_else(|| missing("`common` line"))?,
+            page: page.ok_or_else(|| missing("`page` line"))?,
+            glyphs,
+            kernings,
+        })
+    }
+
+    pub fn texture(&self) -> &GlTexture2D {
+        &self.texture
+    }
+
+    pub(super) fn texture_mut(&mut self) -> &mut GlTexture2D {
+        &mut self.texture
+    }
+
+    /// Swaps in a texture holding the same glyphs, e.g. a larger one. Returns
+    /// the old texture.
+    pub(super) fn replace_texture(&mut self, texture: GlTexture2D) -> GlTexture2D {
+        std::mem::replace(&mut self.texture, texture)
+    }
+
+    /// Adds or replaces a glyph. Its region must lie within the texture.
+    pub(super) fn insert_glyph(&mut self, c: char, glyph: Glyph) {
+        assert!(glyph
+            .region
+            .is_within(self.texture.width(), self.texture.height()));
+        self.descriptor.glyphs.insert(c, glyph);
+    }
+
+    pub(super) fn insert_kerning(&mut self, first: char, second: char, amount: i32) {
+        self.descriptor.kernings.insert((first, second), amount);
+    }
+
+    pub(super) fn clear_glyphs(&mut self) {
+        self.descriptor.glyphs.clear();
+        self.descriptor.kernings.clear();
+    }
+
+    pub fn line_height(&self) -> u32 {
+        self.descriptor.line_height
+    }
+
+    pub fn base(&self) -> u32 {
+        self.descriptor.base
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.descriptor.glyphs.get(&c)
+    }
+
+    pub fn kerning(&self, first: char, second: char) -> i32 {
+        self.descriptor
+            .kernings
+            .get(&(first, second))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Width of a single line of text in texture pixels
+    pub fn line_width(&self, line: &str) -> i32 {
+        let mut width = 0;
+        let mut prev = None;
+        for c in line.chars() {
+            if let Some(glyph) = self.glyph(c) {
+                if let Some(prev) = prev {
+                    width += self.kerning(prev, c);
+                }
+                width += glyph.advance;
+                prev = Some(c);
+            }
+        }
+        width
+    }
+
+    /// Size of `text` in texture pixels, lines are separated by `\n`
+    pub fn measure(&self, text: &str) -> (u32, u32) {
+        let width = text.lines().map(|l| self.line_width(l)).max().unwrap_or(0);
+        let lines = text.lines().count() as u32;
+        (width.max(0) as u32, lines * self.line_height())
+    }
+
+    /// Region of the texture and placement on the canvas of every visible
+    /// glyph of `text`. Characters missing from the font are skipped.
+    pub fn layout(&self, text: &Text) -> Vec<(TextureRegion, Sprite)> {
+        let scale = text.scale;
+        let mut glyphs = Vec::with_capacity(text.text.len());
+
+        for (i, line) in text.text.lines().enumerate() {
+            let width = self.line_width(line) as f32 * scale;
+            let align_offset = match text.align {
+                Align::Left => 0.0,
+                Align::Center => width / 2.0,
+                Align::Right => width,
+            };
+            // Keep glyphs on the pixel grid so nearest filtering doesn't distort them
+            let mut x = (text.pos.x - align_offset).round();
+            let top =
+                text.pos.y + (self.base() as f32 - (i as u32 * self.line_height()) as f32) * scale;
+
+            let mut prev = None;
+            for c in line.chars() {
+                let glyph = match self.glyph(c) {
+                    Some(glyph) => *glyph,
+                    None => continue,
+                };
+                if let Some(prev) = prev {
+                    x += self.kerning(prev, c) as f32 * scale;
+                }
+
+                let region = glyph.region;
+                if region.w > 0 && region.h > 0 {
+                    let bottom = glyph.offset_y + region.h as i32;
+                    let sprite = Sprite::for_region(&region)
+                        .at(
+                            x + glyph.offset_x as f32 * scale,
+                            top - bottom as f32 * scale,
+                        )
+                        .with_scale(scale, scale)
+                        .with_tint(text.color);
+                    glyphs.push((region, sprite));
+                }
+
+                x += glyph.advance as f32 * scale;
+                prev = Some(c);
+            }
+        }
+
+        glyphs
+    }
+}
+
+/// Splits a descriptor line into its tag and `key=value` pairs. Values may be
+/// quoted to contain spaces.
+fn split_line(line: &str) -> Option<(&str, Vec<(&str, String)>)> {
+    let line = line.trim();
+    let tag_end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let tag = &line[..tag_end];
+    if tag.is_empty() {
+        return None;
+    }
+
+    let mut pairs = Vec::new();
+    let mut rest = line[tag_end..].trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        rest = &rest[eq + 1..];
+
+        let value;
+        if rest.starts_with('"') {
+            let end = rest[1..].find('"').map(|e| e + 1).unwrap_or(rest.len());
+            value = rest[1..end].to_string();
+            rest = rest.get(end + 1..).unwrap_or("");
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            value = rest[..end].to_string();
+            rest = &rest[end..];
+        }
+
+        pairs.push((key, value));
+        rest = rest.trim_start();
+    }
+
+    Some((tag, pairs))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// How to place a string on the canvas, the text counterpart of `Sprite`.
+///
+/// `pos` is on the baseline of the first line, at its left edge, center or
+/// right edge depending on `align`. Following lines go down by the font's
+/// line height.
+///
+///     let score = Text::new("42")
+///         .at(144.0, 400.0)
+///         .with_align(Align::Center)
+///         .with_scale(2.0);
+#[derive(Clone, Copy, Debug)]
+pub struct Text<'a> {
+    pub text: &'a str,
+    pub pos: Vec2,
+    pub align: Align,
+    pub scale: f32,
+    pub color: Color,
+}
+
+impl<'a> Text<'a> {
+    pub fn new(text: &'a str) -> Text<'a> {
+        Text {
+            text,
+            pos: Vec2::zero(),
+            align: Align::Left,
+            scale: 1.0,
+            color: Color::white(),
+        }
+    }
+
+    pub fn at(mut self, x: f32, y: f32) -> Text<'a> {
+        self.pos = Vec2::new(x, y);
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Text<'a> {
+        self.align = align;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Text<'a> {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Text<'a> {
+        self.color = color;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTOR: &str = r#"info face="Score Font" size=32 bold=0 italic=0
+common lineHeight=32 base=26 scaleW=256 scaleH=256 pages=1 packed=0
+page id=0 file="score digits.png"
+chars count=2
+char id=48 x=0 y=0 width=16 height=24 xoffset=1 yoffset=4 xadvance=18 page=0 chnl=15
+char id=49   x=16  y=0 width=10 height=24 xoffset=-2 yoffset=4 xadvance=12 page=0 chnl=15
+
+kerning first=49 second=49 amount=-2
+"#;
+
+    fn error(descriptor: &str) -> String {
+        BitmapFont::parse_descriptor(descriptor)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn parses_bmfont_text_format() {
+        let descriptor = BitmapFont::parse_descriptor(DESCRIPTOR).unwrap();
+        assert_eq!(descriptor.line_height, 32);
+        assert_eq!(descriptor.base, 26);
+        assert_eq!(descriptor.page, "score digits.png");
+
+        assert_eq!(descriptor.glyphs.len(), 2);
+        assert_eq!(
+            descriptor.glyphs[&'1'],
+            Glyph {
+                region: TextureRegion::new(16, 0, 10, 24),
+                offset_x: -2,
+                offset_y: 4,
+                advance: 12,
+            }
+        );
+        assert_eq!(descriptor.kernings[&('1', '1')], -2);
+    }
+
+    #[test]
+    fn splits_quoted_values() {
+        let (tag, pairs) = split_line(r#"  info face="Score Font" size=32 charset="""#).unwrap();
+        assert_eq!(tag, "info");
+        assert_eq!(
+            pairs,
+            vec![
+                ("face", "Score Font".to_string()),
+                ("size", "32".to_string()),
+                ("charset", String::new()),
+            ]
+        );
+
+        // An unterminated quote runs to the end of the line
+        let (_, pairs) = split_line(r#"page id=0 file="a b"#).unwrap();
+        assert_eq!(pairs[1], ("file", "a b".to_string()));
+
+        assert!(split_line("   ").is_none());
+    }
+
+    #[test]
+    fn requires_common_and_page() {
+        let no_common = "page id=0 file=\"font.png\"\n";
+        assert!(error(no_common).contains("no `common` line"));
+
+        let no_page = "common lineHeight=32 base=26 pages=1\n";
+        assert!(error(no_page).contains("no `page` line"));
+    }
+
+    #[test]
+    fn rejects_multiple_pages() {
+        let descriptor = DESCRIPTOR.replace("pages=1", "pages=2");
+        assert!(error(&descriptor).contains("line 2: only fonts with one page"));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let missing = DESCRIPTOR.replace(" xadvance=18", "");
+        assert!(error(&missing).contains("line 5: missing `xadvance`"));
+
+        let negative = DESCRIPTOR.replace("width=16", "width=-16");
+        assert!(error(&negative).contains("negative `width`"));
+
+        let not_a_number = DESCRIPTOR.replace("amount=-2", "amount=two");
+        assert!(error(&not_a_number).contains("invalid number for `amount`"));
+
+        let surrogate = DESCRIPTOR.replace("id=48", "id=55296");
+        assert!(error(&surrogate).contains("invalid character for `id`"));
+    }
+}
@@ -0,0 +1,111 @@
+use std::error::Error;
+use std::fmt;
+
+use glutin;
+use glutin::GlContext as GlutinContext;
+
+use super::{Graphics, RenderTarget, ShaderError};
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    Creation(glutin::CreationError),
+    Context(glutin::ContextError),
+    Shader(ShaderError),
+}
+
+impl fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HeadlessError::Creation(ref e) => write!(f, "Failed to create headless context: {}", e),
+            HeadlessError::Context(ref e) => {
+                write!(f, "Failed to make headless context current: {}", e)
+            }
+            HeadlessError::Shader(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for HeadlessError {
+    fn description(&self) -> &str {
+        match *self {
+            HeadlessError::Creation(_) => "failed to create headless context",
+            HeadlessError::Context(_) => "failed to make headless context current",
+            HeadlessError::Shader(_) => "failed to compile shaders",
+        }
+    }
+}
+
+impl From<glutin::CreationError> for HeadlessError {
+    fn from(e: glutin::CreationError) -> HeadlessError {
+        HeadlessError::Creation(e)
+    }
+}
+
+impl From<glutin::ContextError> for HeadlessError {
+    fn from(e: glutin::ContextError) -> HeadlessError {
+        HeadlessError::Context(e)
+    }
+}
+
+impl From<ShaderError> for HeadlessError {
+    fn from(e: ShaderError) -> HeadlessError {
+        HeadlessError::Shader(e)
+    }
+}
+
+/// `Graphics` without a window, e.g. for rendering in tests on machines
+/// without a GPU or display.
+///
+/// The context comes from glutin's headless renderer, which is software Mesa
+/// (OSMesa) on Linux. Everything drawn to the screen ends up in an offscreen
+/// render target of the requested size instead.
+pub struct HeadlessGraphics {
+    // Fields are dropped in order, the context has to outlive everything using it
+    graphics: Graphics,
+    screen: RenderTarget,
+    _context: glutin::HeadlessContext,
+}
+
+impl HeadlessGraphics {
+    pub fn new(w: u32, h: u32) -> Result<HeadlessGraphics, HeadlessError> {
+        let context = glutin::HeadlessRendererBuilder::new(w, h)
+            .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (3, 3)))
+            .with_gl_profile(glutin::GlProfile::Core)
+            .build()?;
+
+        unsafe { context.make_current()? };
+
+        let mut graphics = Graphics::new(|symbol| context.get_proc_address(symbol) as *const _)?;
+        let screen = graphics.create_render_target(w, h);
+        graphics.set_screen(&screen);
+
+        Ok(HeadlessGraphics {
+            graphics,
+            screen,
+            _context: context,
+        })
+    }
+
+    pub fn graphics(&mut self) -> &mut Graphics {
+        &mut self.graphics
+    }
+
+    /// Render target standing in for the window
+    pub fn screen(&self) -> &RenderTarget {
+        &self.screen
+    }
+
+    pub fn width(&self) -> u32 {
+        self.screen.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.screen.height()
+    }
+
+    /// Waits until all submitted rendering has finished.
+    pub fn finish(&mut self) {
+        self.graphics.end_frame();
+        self.graphics.finish();
+    }
+}
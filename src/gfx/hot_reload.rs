@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often `FileWatcher::poll` actually looks at the files
+const POLL_INTERVAL_MS: u64 = 500;
+
+/// Notices changes to files by polling their modification times.
+///
+/// Polling is cheap enough for the handful of files a game has open and needs
+/// no platform specific APIs.
+pub struct FileWatcher {
+    /// Last seen modification time of every watched file, `None` if it
+    /// couldn't be read, e.g. because an editor is in the middle of saving it
+    files: HashMap<PathBuf, Option<SystemTime>>,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new() -> FileWatcher {
+        FileWatcher {
+            files: HashMap::new(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Starts watching `path`. Changes made before this call are not reported.
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+        if !self.files.contains_key(path) {
+            self.files.insert(path.to_path_buf(), modified(path));
+        }
+    }
+
+    pub fn unwatch<P: AsRef<Path>>(&mut self, path: P) {
+        self.files.remove(path.as_ref());
+    }
+
+    /// Files modified since the last poll. Returns nothing if the last poll
+    /// was too recent.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < Duration::from_millis(POLL_INTERVAL_MS) {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = Vec::new();
+        for (path, last_modified) in &mut self.files {
+            let modified = modified(path);
+            // Files that disappear are picked up again once they're back
+            if modified.is_some() && modified != *last_modified {
+                changed.push(path.clone());
+            }
+            *last_modified = modified;
+        }
+        changed
+    }
+}
+
+impl Default for FileWatcher {
+    fn default() -> FileWatcher {
+        FileWatcher::new()
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::File;
+    use std::process;
+
+    use super::*;
+
+    /// Polls right away instead of waiting for `POLL_INTERVAL_MS`
+    fn poll_now(watcher: &mut FileWatcher) -> Vec<PathBuf> {
+        watcher.last_poll = Instant::now() - Duration::from_millis(POLL_INTERVAL_MS);
+        watcher.poll()
+    }
+
+    /// Writes `path` with a modification time of `secs` after the epoch, so
+    /// the test doesn't depend on the file system's timestamp resolution
+    fn write(path: &Path, secs: u64) {
+        let file = File::create(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn reports_modified_deleted_and_recreated_files() {
+        let dir = env::temp_dir().join(format!("flapply_bird_hot_reload_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sprite.png");
+        write(&path, 1);
+
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&path);
+        assert!(poll_now(&mut watcher).is_empty());
+
+        write(&path, 2);
+        assert_eq!(poll_now(&mut watcher), vec![path.clone()]);
+        assert!(poll_now(&mut watcher).is_empty());
+
+        // Not reported while gone, but once it is back
+        fs::remove_file(&path).unwrap();
+        assert!(poll_now(&mut watcher).is_empty());
+        write(&path, 2);
+        assert_eq!(poll_now(&mut watcher), vec![path.clone()]);
+
+        watcher.unwatch(&path);
+        write(&path, 3);
+        assert!(poll_now(&mut watcher).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Why an image couldn't be loaded. `path` is the file it came from, `None`
+/// for images decoded from memory.
+#[derive(Debug)]
+pub enum ImageError {
+    /// The file couldn't be read, e.g. because it is missing
+    Io {
+        path: Option<PathBuf>,
+        error: io::Error,
+    },
+    /// The data is corrupt or in a format the decoder doesn't know
+    Decode {
+        path: Option<PathBuf>,
+        reason: String,
+    },
+    UnsupportedChannels {
+        path: Option<PathBuf>,
+        channels: u32,
+    },
+    /// Components other than 8 bit, e.g. from HDR images
+    UnsupportedComponentType {
+        path: Option<PathBuf>,
+        bytes_per_component: usize,
+    },
+    /// Larger than the biggest texture the GPU supports
+    TooLarge {
+        path: Option<PathBuf>,
+        w: u32,
+        h: u32,
+        max: u32,
+    },
+}
+
+impl ImageError {
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            ImageError::Io { ref path, .. }
+            | ImageError::Decode { ref path, .. }
+            | ImageError::UnsupportedChannels { ref path, .. }
+            | ImageError::UnsupportedComponentType { ref path, .. }
+            | ImageError::TooLarge { ref path, .. } => path.as_ref().map(|p| p.as_path()),
+        }
+    }
+
+    /// Attaches the file the image came from.
+    pub fn with_path<P: AsRef<Path>>(mut self, new_path: P) -> ImageError {
+        match self {
+            ImageError::Io { ref mut path, .. }
+            | ImageError::Decode { ref mut path, .. }
+            | ImageError::UnsupportedChannels { ref mut path, .. }
+            | ImageError::UnsupportedComponentType { ref mut path, .. }
+            | ImageError::TooLarge { ref mut path, .. } => {
+                *path = Some(new_path.as_ref().to_path_buf())
+            }
+        }
+        self
+    }
+
+    /// Whether the file is missing, as opposed to being unreadable or corrupt
+    pub fn is_not_found(&self) -> bool {
+        match *self {
+            ImageError::Io { ref error, .. } => error.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.path() {
+            Some(path) => path.display().to_string(),
+            None => "image".to_string(),
+        };
+
+        match *self {
+            ImageError::Io { ref error, .. } => write!(f, "Failed to read {}: {}", name, error),
+            ImageError::Decode { ref reason, .. } => {
+                write!(f, "Failed to decode {}: {}", name, reason)
+            }
+            ImageError::UnsupportedChannels { channels, .. } => {
+                write!(f, "{} has {} channels, expected 1 to 4", name, channels)
+            }
+            ImageError::UnsupportedComponentType {
+                bytes_per_component,
+                ..
+            } => write!(
+                f,
+                "{} has {} byte components, only 8 bit images are supported",
+                name, bytes_per_component
+            ),
+            ImageError::TooLarge { w, h, max, .. } => write!(
+                f,
+                "{} is {}x{}, larger than the maximum texture size of {}",
+                name, w, h, max
+            ),
+        }
+    }
+}
+
+impl Error for ImageError {
+    fn description(&self) -> &str {
+        match *self {
+            ImageError::Io { .. } => "failed to read image",
+            ImageError::Decode { .. } => "failed to decode image",
+            ImageError::UnsupportedChannels { .. } => "unsupported number of channels",
+            ImageError::UnsupportedComponentType { .. } => "unsupported component type",
+            ImageError::TooLarge { .. } => "image too large",
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(error: io::Error) -> ImageError {
+        ImageError::Io { path: None, error }
+    }
+}
+
+/// For loaders that also read other files, like atlas descriptors. Read
+/// errors keep their kind, everything else is `InvalidData`.
+impl From<ImageError> for io::Error {
+    fn from(e: ImageError) -> io::Error {
+        let kind = match e {
+            ImageError::Io { ref error, .. } => error.kind(),
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, e.to_string())
+    }
+}
@@ -5,15 +5,52 @@ use std::io::prelude::*;
 use std::io;
 use std::ffi::CStr;
 
+mod error;
+mod owned;
+#[cfg(feature = "png")]
+mod png_decoder;
 mod stb_image;
+mod stb_image_write;
 
 use self::stb_image::*;
 
+pub use self::error::ImageError;
+pub use self::owned::Image;
+
 pub enum SupportedImageStorage {
     Rgba8(ImageStorage<Rgba8>),
+    Rgb8(ImageStorage<Rgb8>),
+    La8(ImageStorage<La8>),
     A8(ImageStorage<A8>),
 }
 
+/// Image decoders `GeneralImage` can use. stb_image handles every format,
+/// with the `png` feature PNG files are decoded in Rust instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoder {
+    Stb,
+    #[cfg(feature = "png")]
+    Png,
+}
+
+impl Decoder {
+    /// Decoder `GeneralImage::from_bytes` uses for `bytes`
+    #[cfg(feature = "png")]
+    pub fn for_bytes(bytes: &[u8]) -> Decoder {
+        if png_decoder::is_png(bytes) {
+            Decoder::Png
+        } else {
+            Decoder::Stb
+        }
+    }
+
+    /// Decoder `GeneralImage::from_bytes` uses for `bytes`
+    #[cfg(not(feature = "png"))]
+    pub fn for_bytes(_bytes: &[u8]) -> Decoder {
+        Decoder::Stb
+    }
+}
+
 pub struct GeneralImage {
     w: u32,
     h: u32,
@@ -21,31 +58,144 @@ pub struct GeneralImage {
 }
 
 impl GeneralImage {
-    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<GeneralImage> {
-        let mut file = File::open(&path)?;
+    /// Loads an image with as many channels as the file has. Paletted images
+    /// come out as `Rgb8` or `Rgba8`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<GeneralImage, ImageError> {
+        GeneralImage::load_with_channels(path, None)
+    }
+
+    /// Loads an image, converting it to `desired_channels` channels if given:
+    /// 1 for `A8`, 2 for `La8`, 3 for `Rgb8` and 4 for `Rgba8`.
+    pub fn load_with_channels<P: AsRef<Path>>(
+        path: P,
+        desired_channels: Option<u32>,
+    ) -> Result<GeneralImage, ImageError> {
+        let path = path.as_ref();
+        let read = || -> io::Result<Vec<u8>> {
+            let mut file = File::open(path)?;
+
+            let file_size = file.seek(io::SeekFrom::End(0))? as usize;
+            file.seek(io::SeekFrom::Start(0))?;
+
+            let mut buf = Vec::with_capacity(file_size);
+            file.read_to_end(&mut buf)?;
+            Ok(buf)
+        };
+        let buf = read().map_err(|e| ImageError::from(e).with_path(path))?;
+
+        GeneralImage::from_bytes_with_channels(&buf, desired_channels)
+            .map_err(|e| e.with_path(path))
+    }
+
+    /// Decodes an image file already in memory, e.g. embedded with
+    /// `include_bytes!`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<GeneralImage, ImageError> {
+        GeneralImage::from_bytes_with_channels(bytes, None)
+    }
 
-        let file_size = file.seek(io::SeekFrom::End(0))? as usize;
-        file.seek(io::SeekFrom::Start(0))?;
+    /// Reads `reader` to the end and decodes what it read. Read errors are
+    /// `ImageError::Io`, decoding errors `ImageError::Decode`.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<GeneralImage, ImageError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        GeneralImage::from_bytes(&buf)
+    }
+
+    /// `from_bytes` with a channel count like `load_with_channels`.
+    pub fn from_bytes_with_channels(
+        bytes: &[u8],
+        desired_channels: Option<u32>,
+    ) -> Result<GeneralImage, ImageError> {
+        GeneralImage::from_bytes_with_decoder(bytes, desired_channels, Decoder::for_bytes(bytes))
+    }
+
+    /// `from_bytes_with_channels` with a specific decoder, e.g. to compare them.
+    pub fn from_bytes_with_decoder(
+        bytes: &[u8],
+        desired_channels: Option<u32>,
+        decoder: Decoder,
+    ) -> Result<GeneralImage, ImageError> {
+        match desired_channels {
+            Some(1..=4) | None => (),
+            Some(channels) => {
+                return Err(ImageError::UnsupportedChannels {
+                    path: None,
+                    channels,
+                })
+            }
+        }
 
-        let mut buf = Vec::with_capacity(file_size);
-        file.read_to_end(&mut buf)?;
+        match decoder {
+            Decoder::Stb => (),
+            #[cfg(feature = "png")]
+            Decoder::Png => return png_decoder::decode(bytes, desired_channels),
+        }
 
-        let stbi = StbImage::<u8>::load_from_memory(&mut buf)?;
+        let stbi = StbImage::<u8>::load_from_memory(bytes, desired_channels.unwrap_or(0) as i32)?;
 
         Ok(GeneralImage {
             w: stbi.w as u32,
             h: stbi.h as u32,
             storage: match stbi.n {
                 4 => SupportedImageStorage::Rgba8(ImageStorage::<Rgba8>::new(stbi)),
+                3 => SupportedImageStorage::Rgb8(ImageStorage::<Rgb8>::new(stbi)),
+                2 => SupportedImageStorage::La8(ImageStorage::<La8>::new(stbi)),
                 1 => SupportedImageStorage::A8(ImageStorage::<A8>::new(stbi)),
-                _ => unimplemented!(),
+                n => {
+                    return Err(ImageError::UnsupportedChannels {
+                        path: None,
+                        channels: n as u32,
+                    })
+                }
             },
         })
     }
 
+    /// Image from 8 bit pixels with `channels` interleaved channels, rows top
+    /// to bottom. The channels pick the pixel type like `load_with_channels`.
+    /// Fails with `ImageError::Decode` if `data` is not exactly that long.
+    pub fn from_vec(
+        w: u32,
+        h: u32,
+        channels: u32,
+        data: Vec<u8>,
+    ) -> Result<GeneralImage, ImageError> {
+        let expected_len = (w as usize)
+            .checked_mul(h as usize)
+            .and_then(|n| n.checked_mul(channels as usize));
+        if (1..=4).contains(&channels) && expected_len != Some(data.len()) {
+            return Err(ImageError::Decode {
+                path: None,
+                reason: format!(
+                    "{} bytes don't fit {}x{} pixels with {} channels",
+                    data.len(),
+                    w,
+                    h,
+                    channels
+                ),
+            });
+        }
+
+        let storage = match channels {
+            4 => SupportedImageStorage::Rgba8(ImageStorage::from_vec(w, h, data)),
+            3 => SupportedImageStorage::Rgb8(ImageStorage::from_vec(w, h, data)),
+            2 => SupportedImageStorage::La8(ImageStorage::from_vec(w, h, data)),
+            1 => SupportedImageStorage::A8(ImageStorage::from_vec(w, h, data)),
+            _ => {
+                return Err(ImageError::UnsupportedChannels {
+                    path: None,
+                    channels,
+                })
+            }
+        };
+        Ok(GeneralImage { w, h, storage })
+    }
+
     pub fn num_component(&self) -> u32 {
         match self.storage {
             SupportedImageStorage::Rgba8(_) => Rgba8::NUM_COMPONENT,
+            SupportedImageStorage::Rgb8(_) => Rgb8::NUM_COMPONENT,
+            SupportedImageStorage::La8(_) => La8::NUM_COMPONENT,
             SupportedImageStorage::A8(_) => A8::NUM_COMPONENT,
         }
     }
@@ -53,6 +203,8 @@ impl GeneralImage {
     pub fn num_bytes_per_component(&self) -> usize {
         match self.storage {
             SupportedImageStorage::Rgba8(_) => <Rgba8 as Pixel>::Component::NUM_BYTES,
+            SupportedImageStorage::Rgb8(_) => <Rgb8 as Pixel>::Component::NUM_BYTES,
+            SupportedImageStorage::La8(_) => <La8 as Pixel>::Component::NUM_BYTES,
             SupportedImageStorage::A8(_) => <A8 as Pixel>::Component::NUM_BYTES,
         }
     }
@@ -60,6 +212,8 @@ impl GeneralImage {
     pub fn num_bytes_per_pixel(&self) -> usize {
         match self.storage {
             SupportedImageStorage::Rgba8(_) => <Rgba8 as Pixel>::NUM_BYTES,
+            SupportedImageStorage::Rgb8(_) => <Rgb8 as Pixel>::NUM_BYTES,
+            SupportedImageStorage::La8(_) => <La8 as Pixel>::NUM_BYTES,
             SupportedImageStorage::A8(_) => <A8 as Pixel>::NUM_BYTES,
         }
     }
@@ -79,11 +233,40 @@ impl GeneralImage {
     pub fn bytes(&self) -> &[u8] {
         match self.storage {
             SupportedImageStorage::Rgba8(ref storage) => storage.bytes(),
+            SupportedImageStorage::Rgb8(ref storage) => storage.bytes(),
+            SupportedImageStorage::La8(ref storage) => storage.bytes(),
             SupportedImageStorage::A8(ref storage) => storage.bytes(),
         }
     }
+
+    /// Copy with 4 channels. `A8` images become white with their alpha, like
+    /// they are drawn.
+    pub fn to_rgba(&self) -> RgbaImage {
+        let bytes = self.bytes();
+        let pixels = match self.storage {
+            SupportedImageStorage::Rgba8(_) => {
+                bytes.chunks(4).map(Rgba8::from_components).collect()
+            }
+            SupportedImageStorage::Rgb8(_) => bytes
+                .chunks(3)
+                .map(|p| Rgba8::new(p[0], p[1], p[2], 255))
+                .collect(),
+            SupportedImageStorage::La8(_) => bytes
+                .chunks(2)
+                .map(|p| Rgba8::new(p[0], p[0], p[0], p[1]))
+                .collect(),
+            SupportedImageStorage::A8(_) => bytes
+                .iter()
+                .map(|&a| Rgba8::new(255, 255, 255, a))
+                .collect(),
+        };
+        Image::from_vec(self.w, self.h, pixels)
+    }
 }
 
+/// Owned RGBA image with 8 bits per channel, rows stored top to bottom
+pub type RgbaImage = Image<Rgba8>;
+
 pub trait Component {
     const NUM_BYTES: usize;
 }
@@ -102,14 +285,25 @@ pub trait Pixel {
     const NUM_BYTES: usize = Self::Component::NUM_BYTES * Self::NUM_COMPONENT as usize;
 
     fn component(&self, n: u32) -> Option<&Self::Component>;
+
+    /// Pixel from its first `NUM_COMPONENT` components. Panics if there are
+    /// fewer.
+    fn from_components(components: &[Self::Component]) -> Self;
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[repr(C)]
 pub struct Rgba8 {
-    r: u8,
-    g: u8,
-    b: u8,
-    a: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba8 {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Rgba8 {
+        Rgba8 { r, g, b, a }
+    }
 }
 
 impl Pixel for Rgba8 {
@@ -125,11 +319,85 @@ impl Pixel for Rgba8 {
             _ => None,
         }
     }
+
+    fn from_components(c: &[u8]) -> Rgba8 {
+        Rgba8::new(c[0], c[1], c[2], c[3])
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct Rgb8 {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb8 {
+    pub fn new(r: u8, g: u8, b: u8) -> Rgb8 {
+        Rgb8 { r, g, b }
+    }
+}
+
+impl Pixel for Rgb8 {
+    type Component = u8;
+    const NUM_COMPONENT: u32 = 3;
+
+    fn component(&self, n: u32) -> Option<&Self::Component> {
+        match n {
+            0 => Some(&self.r),
+            1 => Some(&self.g),
+            2 => Some(&self.b),
+            _ => None,
+        }
+    }
+
+    fn from_components(c: &[u8]) -> Rgb8 {
+        Rgb8::new(c[0], c[1], c[2])
+    }
 }
 
+/// Grey with alpha
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct La8 {
+    pub l: u8,
+    pub a: u8,
+}
+
+impl La8 {
+    pub fn new(l: u8, a: u8) -> La8 {
+        La8 { l, a }
+    }
+}
+
+impl Pixel for La8 {
+    type Component = u8;
+    const NUM_COMPONENT: u32 = 2;
+
+    fn component(&self, n: u32) -> Option<&Self::Component> {
+        match n {
+            0 => Some(&self.l),
+            1 => Some(&self.a),
+            _ => None,
+        }
+    }
+
+    fn from_components(c: &[u8]) -> La8 {
+        La8::new(c[0], c[1])
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[repr(C)]
 pub struct A8 {
-    a: u8,
+    pub a: u8,
+}
+
+impl A8 {
+    pub fn new(a: u8) -> A8 {
+        A8 { a }
+    }
 }
 
 impl Pixel for A8 {
@@ -142,10 +410,22 @@ impl Pixel for A8 {
             _ => None,
         }
     }
+
+    fn from_components(c: &[u8]) -> A8 {
+        A8::new(c[0])
+    }
+}
+
+/// Decoded pixels, allocated either by stb_image or by Rust
+enum Buffer<C: Component> {
+    Stb(StbImage<C>),
+    Vec(Vec<C>),
 }
 
 pub struct ImageStorage<P: Pixel> {
-    stbi: StbImage<P::Component>,
+    w: u32,
+    h: u32,
+    buffer: Buffer<P::Component>,
     _phantom: std::marker::PhantomData<P>,
 }
 
@@ -155,35 +435,50 @@ impl<P: Pixel> ImageStorage<P> {
         assert!(stbi.w * stbi.n == P::NUM_BYTES as i32 * stbi.w);
 
         ImageStorage {
-            stbi,
+            w: stbi.w as u32,
+            h: stbi.h as u32,
+            buffer: Buffer::Stb(stbi),
             _phantom: std::marker::PhantomData,
         }
     }
 
-    pub fn pixel(&self, x: u32, y: u32) -> Option<&P> {
-        let w = self.stbi.w as u32;
-        let h = self.stbi.h as u32;
+    /// `data` holds the components of `w * h` pixels, rows top to bottom.
+    pub fn from_vec(w: u32, h: u32, data: Vec<P::Component>) -> ImageStorage<P> {
+        assert!(data.len() == w as usize * h as usize * P::NUM_COMPONENT as usize);
 
-        if x >= w || y >= h {
-            return None;
+        ImageStorage {
+            w,
+            h,
+            buffer: Buffer::Vec(data),
+            _phantom: std::marker::PhantomData,
         }
+    }
 
-        let n = self.stbi.n as u32;
-        let offset = y * w * n + x * n;
-        unsafe {
-            let p = self.stbi.data.offset(offset as isize);
-            let pixel = p as *mut P;
-
-            Some(&*pixel)
+    pub fn pixel(&self, x: u32, y: u32) -> Option<P> {
+        if x >= self.w || y >= self.h {
+            return None;
         }
+
+        let n = P::NUM_COMPONENT as usize;
+        let offset = (y as usize * self.w as usize + x as usize) * n;
+        Some(P::from_components(&self.data()[offset..offset + n]))
     }
 
     pub fn data(&self) -> &[P::Component] {
-        self.stbi.data()
+        match self.buffer {
+            Buffer::Stb(ref stbi) => stbi.data(),
+            Buffer::Vec(ref data) => data,
+        }
     }
 
     pub fn bytes(&self) -> &[u8] {
-        self.stbi.bytes()
+        let data = self.data();
+        unsafe {
+            std::slice::from_raw_parts(
+                data.as_ptr() as *const u8,
+                data.len() * <P::Component as Component>::NUM_BYTES,
+            )
+        }
     }
 }
 
@@ -207,34 +502,37 @@ impl<C: Component> StbImage<C> {
     fn data(&self) -> &[C] {
         unsafe { std::slice::from_raw_parts(self.data, (self.n * self.w * self.h) as usize) }
     }
-
-    fn bytes(&self) -> &[u8] {
-        unsafe {
-            std::slice::from_raw_parts(self.data as *mut u8, (self.n * self.w * self.h) as usize)
-        }
-    }
 }
 
 impl StbImage<u8> {
-    fn load_from_memory(buf: &mut [u8]) -> io::Result<StbImage<u8>> {
+    /// `desired_channels` 0 keeps the channels of the file.
+    fn load_from_memory(buf: &[u8], desired_channels: i32) -> Result<StbImage<u8>, ImageError> {
         let mut stbi = StbImage::empty();
         unsafe {
             stbi.data = stbi_load_from_memory(
-                buf.as_mut_ptr(),
+                buf.as_ptr(),
                 buf.len() as i32,
                 &mut stbi.w,
                 &mut stbi.h,
                 &mut stbi.n,
-                0,
+                desired_channels,
             );
         };
 
         if stbi.data.is_null() {
-            return Err(io::Error::new(io::ErrorKind::Other, unsafe {
-                CStr::from_ptr(stbi_failure_reason())
-                    .to_string_lossy()
-                    .into_owned()
-            }));
+            return Err(ImageError::Decode {
+                path: None,
+                reason: unsafe {
+                    CStr::from_ptr(stbi_failure_reason())
+                        .to_string_lossy()
+                        .into_owned()
+                },
+            });
+        }
+
+        // `n` is what the file has, the data is what was asked for
+        if desired_channels != 0 {
+            stbi.n = desired_channels;
         }
 
         Ok(stbi)
@@ -246,3 +544,85 @@ impl<C: Component> Drop for StbImage<C> {
         unsafe { stbi_image_free(self.data as *mut u8) };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn failing_reader_is_io_error() {
+        match GeneralImage::from_reader(FailingReader) {
+            Err(ImageError::Io { path: None, error }) => {
+                assert_eq!(error.kind(), io::ErrorKind::BrokenPipe)
+            }
+            other => panic!("expected an Io error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn garbage_is_decode_error() {
+        let garbage = b"definitely not an image";
+        match GeneralImage::from_bytes(garbage) {
+            Err(ImageError::Decode { path: None, .. }) => (),
+            other => panic!("expected a Decode error, got {:?}", other.err()),
+        }
+        match GeneralImage::from_reader(&garbage[..]) {
+            Err(ImageError::Decode { path: None, .. }) => (),
+            other => panic!("expected a Decode error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn missing_file_is_not_found() {
+        let path = Path::new("tests/images/missing.png");
+        let e = GeneralImage::load(path).err().unwrap();
+        match e {
+            ImageError::Io { .. } => (),
+            ref other => panic!("expected an Io error, got {:?}", other),
+        }
+        assert!(e.is_not_found());
+        assert_eq!(e.path(), Some(path));
+        assert!(e.to_string().contains("missing.png"));
+    }
+
+    #[test]
+    fn with_path_attaches_path() {
+        let e = GeneralImage::from_bytes(b"garbage").err().unwrap();
+        assert!(!e.is_not_found());
+        assert_eq!(e.path(), None);
+
+        let e = e.with_path("sprites/bird.png");
+        assert_eq!(e.path(), Some(Path::new("sprites/bird.png")));
+        let message = e.to_string();
+        assert!(message.starts_with("Failed to decode sprites/bird.png"));
+    }
+
+    #[test]
+    fn from_vec_checks_length() {
+        let image = GeneralImage::from_vec(3, 2, 3, vec![0; 18]).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+
+        for &len in &[0, 17, 19] {
+            match GeneralImage::from_vec(3, 2, 3, vec![0; len]) {
+                Err(ImageError::Decode { .. }) => (),
+                other => panic!("expected a Decode error, got {:?}", other.err()),
+            }
+        }
+        match GeneralImage::from_vec(u32::MAX, u32::MAX, 4, Vec::new()) {
+            Err(ImageError::Decode { .. }) => (),
+            other => panic!("expected a Decode error, got {:?}", other.err()),
+        }
+        match GeneralImage::from_vec(1, 1, 5, vec![0; 5]) {
+            Err(ImageError::UnsupportedChannels { channels: 5, .. }) => (),
+            other => panic!("expected UnsupportedChannels, got {:?}", other.err()),
+        }
+    }
+}
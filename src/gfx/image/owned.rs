@@ -0,0 +1,315 @@
+use std;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::os::raw::{c_int, c_void};
+use std::path::Path;
+use std::slice;
+
+use super::stb_image_write::*;
+use super::{GeneralImage, ImageError, Pixel, Rgba8};
+
+/// Owned image with pixels of type `P`, rows stored top to bottom.
+///
+/// Unlike decoded images it can be modified, e.g. to generate textures in
+/// code, and then turned into a `GeneralImage` for upload.
+///
+///     let mut image = Image::<Rgba8>::new(2, 2);
+///     image.put_pixel(1, 0, Rgba8::new(255, 0, 0, 255));
+///     let texture = gfx.create_texture_from_image(&image.into())?;
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image<P: Pixel> {
+    w: u32,
+    h: u32,
+    pixels: Vec<P>,
+}
+
+impl<P: Pixel> Image<P> {
+    /// Image with every pixel set to its default, i.e. all channels zero
+    pub fn new(w: u32, h: u32) -> Image<P>
+    where
+        P: Clone + Default,
+    {
+        Image::from_pixel(w, h, P::default())
+    }
+
+    pub fn from_pixel(w: u32, h: u32, pixel: P) -> Image<P>
+    where
+        P: Clone,
+    {
+        Image::from_vec(w, h, vec![pixel; w as usize * h as usize])
+    }
+
+    pub fn from_vec(w: u32, h: u32, pixels: Vec<P>) -> Image<P> {
+        assert!(pixels.len() == w as usize * h as usize);
+        Image { w, h, pixels }
+    }
+
+    /// Image from interleaved components, e.g. the bytes of an `Rgba8` image.
+    pub fn from_components(w: u32, h: u32, components: &[P::Component]) -> Image<P> {
+        let n = P::NUM_COMPONENT as usize;
+        assert!(components.len() == w as usize * h as usize * n);
+
+        let pixels = components.chunks(n).map(P::from_components).collect();
+        Image::from_vec(w, h, pixels)
+    }
+
+    /// Loads an image file, converting it to the channels of `P`.
+    pub fn load<Q: AsRef<Path>>(path: Q) -> Result<Image<P>, ImageError>
+    where
+        P: Pixel<Component = u8>,
+    {
+        let image = GeneralImage::load_with_channels(path, Some(P::NUM_COMPONENT))?;
+        Ok(Image::from_components(
+            image.width(),
+            image.height(),
+            image.bytes(),
+        ))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.w
+    }
+
+    pub fn height(&self) -> u32 {
+        self.h
+    }
+
+    /// Pixel at `(x, y)`, `(0, 0)` is the top left corner
+    pub fn pixel(&self, x: u32, y: u32) -> Option<&P> {
+        let index = self.index(x, y)?;
+        Some(&self.pixels[index])
+    }
+
+    pub fn pixel_mut(&mut self, x: u32, y: u32) -> Option<&mut P> {
+        let index = self.index(x, y)?;
+        Some(&mut self.pixels[index])
+    }
+
+    /// Sets the pixel at `(x, y)`. Panics if it lies outside of the image.
+    pub fn put_pixel(&mut self, x: u32, y: u32, pixel: P) {
+        match self.pixel_mut(x, y) {
+            Some(p) => *p = pixel,
+            None => panic!(
+                "Pixel ({}, {}) is outside of the {}x{} image",
+                x, y, self.w, self.h
+            ),
+        }
+    }
+
+    /// Rows from top to bottom
+    pub fn rows<'a>(&'a self) -> slice::Chunks<'a, P> {
+        self.pixels.chunks(self.w.max(1) as usize)
+    }
+
+    pub fn rows_mut<'a>(&'a mut self) -> slice::ChunksMut<'a, P> {
+        self.pixels.chunks_mut(self.w.max(1) as usize)
+    }
+
+    pub fn pixels(&self) -> &[P] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [P] {
+        &mut self.pixels
+    }
+
+    pub fn into_vec(self) -> Vec<P> {
+        self.pixels
+    }
+
+    pub fn flip_vertically(&mut self) {
+        let w = self.w as usize;
+        let h = self.h as usize;
+        for y in 0..h / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((h - 1 - y) * w);
+            top[y * w..(y + 1) * w].swap_with_slice(&mut bottom[..w]);
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.w || y >= self.h {
+            return None;
+        }
+        Some(y as usize * self.w as usize + x as usize)
+    }
+}
+
+impl Image<Rgba8> {
+    pub fn stride(&self) -> isize {
+        self.w as isize * 4
+    }
+
+    /// The pixels as `r, g, b, a` bytes
+    pub fn bytes(&self) -> &[u8] {
+        // `Rgba8` is `repr(C)` with four `u8`s, so the pixels are plain bytes
+        unsafe { slice::from_raw_parts(self.pixels.as_ptr() as *const u8, self.pixels.len() * 4) }
+    }
+
+    pub fn bytes_mut(&mut self) -> &mut [u8] {
+        unsafe {
+            slice::from_raw_parts_mut(self.pixels.as_mut_ptr() as *mut u8, self.pixels.len() * 4)
+        }
+    }
+
+    /// Converts from pre-multiplied to straight alpha
+    pub fn unpremultiply(&mut self) {
+        for pixel in self.pixels.iter_mut() {
+            let a = pixel.a as u32;
+            if a == 0 || a == 255 {
+                continue;
+            }
+            for c in [&mut pixel.r, &mut pixel.g, &mut pixel.b].iter_mut() {
+                **c = ((**c as u32 * 255 + a / 2) / a).min(255) as u8;
+            }
+        }
+    }
+
+    /// Encodes the image as PNG into `writer`.
+    pub fn write_png<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        extern "C" fn append(context: *mut c_void, data: *mut c_void, size: c_int) {
+            unsafe {
+                let buf = &mut *(context as *mut Vec<u8>);
+                let data = std::slice::from_raw_parts(data as *const u8, size as usize);
+                buf.extend_from_slice(data);
+            }
+        }
+
+        let mut buf = Vec::<u8>::new();
+        let result = unsafe {
+            stbi_write_png_to_func(
+                append,
+                &mut buf as *mut Vec<u8> as *mut c_void,
+                self.w as c_int,
+                self.h as c_int,
+                4,
+                self.bytes().as_ptr() as *const c_void,
+                self.stride() as c_int,
+            )
+        };
+
+        if result == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Failed to encode PNG",
+            ));
+        }
+
+        writer.write_all(&buf)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_png(File::create(path)?)
+    }
+}
+
+impl<P: Pixel<Component = u8>> From<Image<P>> for GeneralImage {
+    fn from(image: Image<P>) -> GeneralImage {
+        let n = P::NUM_COMPONENT;
+        let mut bytes = Vec::with_capacity(image.pixels.len() * n as usize);
+        for pixel in &image.pixels {
+            bytes.extend((0..n).filter_map(|i| pixel.component(i)));
+        }
+
+        GeneralImage::from_vec(image.w, image.h, n, bytes)
+            .expect("Pixel types have 1 to 4 channels")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{La8, Rgb8, A8};
+    use super::*;
+
+    fn numbered(w: u32, h: u32) -> Image<A8> {
+        let pixels = (0..w * h).map(|i| A8::new(i as u8)).collect();
+        Image::from_vec(w, h, pixels)
+    }
+
+    #[test]
+    fn pixel_access_is_bounds_checked() {
+        let mut image = numbered(3, 2);
+        assert_eq!(image.pixel(0, 0), Some(&A8::new(0)));
+        assert_eq!(image.pixel(2, 1), Some(&A8::new(5)));
+        assert_eq!(image.pixel(3, 0), None);
+        assert_eq!(image.pixel(0, 2), None);
+        assert_eq!(image.pixel(u32::MAX, u32::MAX), None);
+
+        *image.pixel_mut(1, 1).unwrap() = A8::new(42);
+        assert_eq!(image.pixels()[4], A8::new(42));
+        assert!(image.pixel_mut(3, 1).is_none());
+    }
+
+    #[test]
+    fn put_pixel() {
+        let mut image = Image::<A8>::new(2, 2);
+        image.put_pixel(1, 0, A8::new(7));
+        assert_eq!(
+            image.into_vec(),
+            vec![A8::new(0), A8::new(7), A8::new(0), A8::new(0)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Pixel (2, 0) is outside of the 2x2 image")]
+    fn put_pixel_outside_panics() {
+        Image::<A8>::new(2, 2).put_pixel(2, 0, A8::new(7));
+    }
+
+    #[test]
+    fn rows_top_to_bottom() {
+        let image = numbered(2, 3);
+        let rows: Vec<_> = image.rows().map(|row| row.to_vec()).collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![A8::new(0), A8::new(1)],
+                vec![A8::new(2), A8::new(3)],
+                vec![A8::new(4), A8::new(5)],
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_of_empty_images() {
+        let mut image = Image::<A8>::new(0, 3);
+        assert_eq!(image.rows().count(), 0);
+        assert_eq!(image.rows_mut().count(), 0);
+        assert_eq!(Image::<A8>::new(3, 0).rows().count(), 0);
+    }
+
+    #[test]
+    fn flip_vertically() {
+        let mut image = numbered(2, 3);
+        image.flip_vertically();
+        let values: Vec<_> = image.pixels().iter().map(|p| p.a).collect();
+        assert_eq!(values, vec![4, 5, 2, 3, 0, 1]);
+    }
+
+    fn round_trip<P>(pixels: Vec<P>)
+    where
+        P: Pixel<Component = u8> + Clone + PartialEq + ::std::fmt::Debug,
+    {
+        let image = Image::from_vec(2, 1, pixels);
+        let general = GeneralImage::from(image.clone());
+        assert_eq!((general.width(), general.height()), (2, 1));
+        assert_eq!(general.num_component(), P::NUM_COMPONENT);
+        assert_eq!(Image::from_components(2, 1, general.bytes()), image);
+    }
+
+    #[test]
+    fn into_general_image_round_trip() {
+        round_trip(vec![Rgba8::new(1, 2, 3, 4), Rgba8::new(5, 6, 7, 8)]);
+        round_trip(vec![Rgb8::new(1, 2, 3), Rgb8::new(4, 5, 6)]);
+        round_trip(vec![La8::new(1, 2), La8::new(3, 4)]);
+        round_trip(vec![A8::new(1), A8::new(2)]);
+    }
+
+    #[test]
+    fn rgba_bytes() {
+        let mut image = Image::from_vec(2, 1, vec![Rgba8::new(1, 2, 3, 4), Rgba8::new(5, 6, 7, 8)]);
+        assert_eq!(image.bytes(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+        image.bytes_mut()[6] = 0;
+        assert_eq!(image.pixel(1, 0), Some(&Rgba8::new(5, 6, 0, 8)));
+    }
+}
@@ -0,0 +1,97 @@
+use std::io::Cursor;
+
+use png;
+
+use super::{GeneralImage, ImageError};
+
+/// First bytes of every PNG file
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+pub fn is_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(SIGNATURE)
+}
+
+/// Decodes a PNG into 8 bit channels the same way stb_image does: palettes
+/// and transparency chunks are expanded and 16 bit channels keep their high
+/// byte.
+pub fn decode(bytes: &[u8], desired_channels: Option<u32>) -> Result<GeneralImage, ImageError> {
+    let decode_error = |e: png::DecodingError| ImageError::Decode {
+        path: None,
+        reason: e.to_string(),
+    };
+
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    // 16 bit channels are reduced after converting channels, like stb_image does
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(decode_error)?;
+
+    let size = reader
+        .output_buffer_size()
+        .ok_or_else(|| ImageError::Decode {
+            path: None,
+            reason: "image is too large".to_string(),
+        })?;
+    let mut data = vec![0; size];
+    let info = reader.next_frame(&mut data).map_err(decode_error)?;
+    data.truncate(info.buffer_size());
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => unreachable!("palettes are expanded"),
+    };
+
+    let is_16_bit = info.bit_depth == png::BitDepth::Sixteen;
+    let samples = if is_16_bit {
+        data.chunks(2)
+            .map(|s| u16::from(s[0]) << 8 | u16::from(s[1]))
+            .collect()
+    } else {
+        data.iter().map(|&s| u16::from(s)).collect::<Vec<_>>()
+    };
+    let (samples, channels) = match desired_channels {
+        Some(desired) if desired != channels => {
+            let max = if is_16_bit { 0xffff } else { 0xff };
+            (convert_channels(&samples, channels, desired, max), desired)
+        }
+        _ => (samples, channels),
+    };
+
+    let shift = if is_16_bit { 8 } else { 0 };
+    let data = samples.iter().map(|&s| (s >> shift) as u8).collect();
+    GeneralImage::from_vec(info.width, info.height, channels, data)
+}
+
+/// Converts between channel counts with the formulas of stb_image. `max` is
+/// the value of an opaque alpha channel.
+fn convert_channels(samples: &[u16], from: u32, to: u32, max: u16) -> Vec<u16> {
+    let mut converted = Vec::with_capacity(samples.len() / from as usize * to as usize);
+    for p in samples.chunks(from as usize) {
+        let (grey, alpha) = match from {
+            1 => (p[0], max),
+            2 => (p[0], p[1]),
+            3 => (luma(p), max),
+            _ => (luma(p), p[3]),
+        };
+        let (r, g, b) = if from >= 3 {
+            (p[0], p[1], p[2])
+        } else {
+            (grey, grey, grey)
+        };
+
+        match to {
+            1 => converted.push(grey),
+            2 => converted.extend_from_slice(&[grey, alpha]),
+            3 => converted.extend_from_slice(&[r, g, b]),
+            _ => converted.extend_from_slice(&[r, g, b, alpha]),
+        }
+    }
+    converted
+}
+
+/// stb_image's integer approximation of luma
+fn luma(p: &[u16]) -> u16 {
+    ((u32::from(p[0]) * 77 + u32::from(p[1]) * 150 + u32::from(p[2]) * 29) >> 8) as u16
+}
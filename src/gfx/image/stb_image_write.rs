@@ -0,0 +1,15 @@
+use std::os::raw::{c_int, c_void};
+
+pub type StbiWriteFunc = extern "C" fn(context: *mut c_void, data: *mut c_void, size: c_int);
+
+extern "C" {
+    pub fn stbi_write_png_to_func(
+        func: StbiWriteFunc,
+        context: *mut c_void,
+        w: c_int,
+        h: c_int,
+        comp: c_int,
+        data: *const c_void,
+        stride_in_bytes: c_int,
+    ) -> c_int;
+}
@@ -1,52 +1,563 @@
 extern crate gl;
 
+pub mod atlas;
+pub mod camera;
+pub mod color;
+pub mod font;
+pub mod headless;
+pub mod hot_reload;
 pub mod image;
+pub mod object;
 pub mod program;
+pub mod render_target;
+pub mod resources;
+pub mod scaling;
+pub mod sprite;
+pub mod truetype;
 
 use std;
+use std::fs::File;
 use std::io;
+use std::io::prelude::*;
 use std::path::Path;
 use std::os::raw::c_void;
 use std::ffi::CStr;
-
-use math::Trans2;
-
-use self::image::GeneralImage;
-use self::program::draw_texture::DrawTextureProgram;
+use std::rc::Rc;
+
+use math::{Rect, Trans2, Vec2};
+
+use self::atlas::{Atlas, TextureRegion};
+use self::hot_reload::FileWatcher;
+use self::image::{GeneralImage, RgbaImage};
+use self::object::ContextState;
+use self::program::draw_texture::{self, DrawTextureProgram};
+
+pub use self::camera::Camera;
+pub use self::color::Color;
+pub use self::font::{Align, BitmapFont, Text};
+pub use self::headless::{HeadlessError, HeadlessGraphics};
+pub use self::image::ImageError;
+pub use self::program::{ShaderError, ShaderStage};
+pub use self::object::{GlObject, GlObjectCounts, GlObjectKind};
+pub use self::render_target::RenderTarget;
+pub use self::resources::{Resources, TextureHandle, TextureRef};
+pub use self::scaling::{Scaler, ScalingMode, Viewport};
+pub use self::sprite::Sprite;
+pub use self::truetype::{GlyphCache, TrueTypeFont};
+
+/// Default size of the logical canvas in pixels, see `Graphics::set_camera`
+pub const CANVAS_WIDTH: u32 = 288;
+pub const CANVAS_HEIGHT: u32 = 512;
 
 pub struct Graphics {
     gl_context: GlContext,
     draw_texture_program: DrawTextureProgram,
+    scaler: Scaler,
+    resources: Resources,
+    /// Watches loaded textures and shader sources while hot reloading is on
+    watcher: Option<FileWatcher>,
+    /// Framebuffer standing in for the window, `0` unless headless
+    screen_fbo: u32,
+    /// `(fbo, w, h)` of the render target draws currently go to if it isn't the screen
+    render_target: Option<(u32, u32, u32)>,
+    last_frame_draw_calls: u32,
 }
 
 impl Graphics {
-    pub fn new<F>(loadfn: F) -> Graphics
+    /// Sets up rendering with the GL functions `loadfn` looks up. Fails if the
+    /// built in shaders don't compile on this driver.
+    pub fn new<F>(loadfn: F) -> Result<Graphics, ShaderError>
     where
         F: FnMut(&str) -> *const c_void,
     {
         let mut gl_context = GlContext::new(loadfn);
-        Graphics {
-            draw_texture_program: DrawTextureProgram::new(&mut gl_context),
+        let mut graphics = Graphics {
+            draw_texture_program: DrawTextureProgram::new(&mut gl_context)?,
             gl_context,
+            scaler: Scaler::new(ScalingMode::AspectFit, CANVAS_WIDTH, CANVAS_HEIGHT),
+            resources: Resources::new(),
+            watcher: None,
+            screen_fbo: 0,
+            render_target: None,
+            last_frame_draw_calls: 0,
+        };
+        graphics.set_camera(&Camera::new(CANVAS_WIDTH as f32, CANVAS_HEIGHT as f32));
+        graphics.apply_scaler();
+        Ok(graphics)
+    }
+
+    /// Sets the projection used by all following draws. Typically called once
+    /// per frame before drawing.
+    pub fn set_camera(&mut self, camera: &Camera) {
+        self.draw_texture_program
+            .set_mvp(&mut self.gl_context, camera.projection());
+    }
+
+    /// Sets the window area rendered into, in window pixels with the origin at
+    /// the bottom left.
+    pub fn set_viewport(&mut self, x: i32, y: i32, w: u32, h: u32) {
+        self.flush();
+        unsafe { gl::Viewport(x, y, w as i32, h as i32) };
+    }
+
+    /// Fits the canvas into a window of the new size according to the scaling mode.
+    pub fn resize(&mut self, window_w: u32, window_h: u32) {
+        self.scaler.set_window_size(window_w, window_h);
+        self.apply_scaler();
+    }
+
+    pub fn scaling_mode(&self) -> ScalingMode {
+        self.scaler.mode()
+    }
+
+    pub fn set_scaling_mode(&mut self, mode: ScalingMode) {
+        self.scaler.set_mode(mode);
+        self.apply_scaler();
+    }
+
+    /// Maps a window position, e.g. of the mouse cursor, to canvas pixels.
+    /// Returns `None` outside of the canvas.
+    pub fn window_to_canvas(&self, x: f64, y: f64) -> Option<Vec2> {
+        self.scaler.window_to_canvas(x, y)
+    }
+
+    fn apply_scaler(&mut self) {
+        // Render targets always use their full size
+        if self.render_target.is_some() {
+            return;
         }
+
+        let viewport = self.scaler.viewport();
+        self.set_viewport(viewport.x, viewport.y, viewport.w, viewport.h);
+    }
+
+    /// Creates a `w` x `h` render target. Draws keep going where they went
+    /// before, use `set_render_target` to draw into it.
+    pub fn create_render_target(&mut self, w: u32, h: u32) -> RenderTarget {
+        // Setting up the target binds its texture and framebuffer
+        self.flush();
+        RenderTarget::new(&mut self.gl_context, w, h)
+    }
+
+    /// Redirects all following draws, including `clear`, into `target`, or
+    /// back to the window with `None`.
+    pub fn set_render_target(&mut self, target: Option<&RenderTarget>) {
+        self.flush();
+
+        match target {
+            Some(target) => {
+                self.render_target = Some((target.fbo(), target.width(), target.height()));
+                self.gl_context.bind_framebuffer(target.fbo());
+                self.set_viewport(0, 0, target.width(), target.height());
+            }
+            None => {
+                self.render_target = None;
+                let screen_fbo = self.screen_fbo;
+                self.gl_context.bind_framebuffer(screen_fbo);
+                self.apply_scaler();
+            }
+        }
+    }
+
+    /// Reads back the whole framebuffer draws currently go to, i.e. the bound
+    /// render target or the screen.
+    ///
+    /// Rows are returned top to bottom like in image files. Pixels are in
+    /// pre-multiplied alpha unless `unpremultiply` is set.
+    pub fn read_pixels(&mut self, unpremultiply: bool) -> RgbaImage {
+        self.flush();
+
+        let (w, h) = match self.render_target {
+            Some((_, w, h)) => (w, h),
+            None => self.scaler.window_size(),
+        };
+
+        let mut image = RgbaImage::new(w, h);
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+            gl::PixelStorei(gl::PACK_ROW_LENGTH, 0);
+            gl::ReadPixels(
+                0,
+                0,
+                w as i32,
+                h as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                image.bytes_mut().as_mut_ptr() as *mut c_void,
+            );
+        }
+
+        // OpenGL returns the bottom row first
+        image.flip_vertically();
+        if unpremultiply {
+            image.unpremultiply();
+        }
+
+        image
+    }
+
+    /// Makes `target` the screen, i.e. what `set_render_target(None)` goes back
+    /// to, for contexts without a window.
+    fn set_screen(&mut self, target: &RenderTarget) {
+        self.screen_fbo = target.fbo();
+        self.resize(target.width(), target.height());
+        self.set_render_target(None);
     }
 
     pub fn clear(&mut self) {
+        self.flush();
         unsafe { gl::Clear(gl::COLOR_BUFFER_BIT) };
     }
 
-    pub fn load_texture<P: AsRef<Path>>(&mut self, path: P) -> io::Result<GlTexture2D> {
-        let image = image::GeneralImage::load(path)?;
-        Ok(GlTexture2D::from_image(&mut self.gl_context, &image))
+    /// Submits all batched draws to the GPU.
+    pub fn flush(&mut self) {
+        self.draw_texture_program.flush(&mut self.gl_context);
+        self.gl_context.delete_garbage();
     }
 
-    pub fn draw_texture(&mut self, texture: &GlTexture2D) {
-        self.draw_texture_program
-            .draw(&mut self.gl_context, texture, Trans2::identity());
+    /// Flushes the frame. Must be called before swapping buffers.
+    pub fn end_frame(&mut self) {
+        self.flush();
+        self.last_frame_draw_calls = self.draw_texture_program.num_draw_calls();
+        self.draw_texture_program.reset_num_draw_calls();
+
+        self.reload_changed_files();
+    }
+
+    /// Turns watching the files of loaded textures and shaders on or off.
+    ///
+    /// While on, `end_frame` re-uploads textures and recompiles shaders whose
+    /// files changed. If that fails the old version stays in use and the error
+    /// is logged.
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        if !enabled {
+            self.watcher = None;
+            return;
+        }
+        if self.watcher.is_some() {
+            return;
+        }
+
+        let mut watcher = FileWatcher::new();
+        watcher.watch(draw_texture::VERTEX_SHADER_PATH);
+        watcher.watch(draw_texture::FRAGMENT_SHADER_PATH);
+        for path in self.resources.paths() {
+            watcher.watch(path);
+        }
+        self.watcher = Some(watcher);
+    }
+
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    fn reload_changed_files(&mut self) {
+        let changed = match self.watcher {
+            Some(ref mut watcher) => watcher.poll(),
+            None => return,
+        };
+
+        let mut shaders_changed = false;
+        for path in changed {
+            if path == Path::new(draw_texture::VERTEX_SHADER_PATH)
+                || path == Path::new(draw_texture::FRAGMENT_SHADER_PATH)
+            {
+                shaders_changed = true;
+                continue;
+            }
+
+            let handle = match self.resources.find(&path) {
+                Some(handle) => handle,
+                None => {
+                    // Unloaded since it was loaded
+                    if let Some(ref mut watcher) = self.watcher {
+                        watcher.unwatch(&path);
+                    }
+                    continue;
+                }
+            };
+
+            match self.load_texture_uncached(&path) {
+                Ok(texture) => {
+                    // The old texture is deleted on the next flush
+                    let _ = self.resources.replace(handle, texture);
+                    println!("Reloaded {}", path.display());
+                }
+                // The error names the file
+                Err(e) => println!("Failed to reload texture: {}", e),
+            }
+        }
+
+        if shaders_changed {
+            self.reload_shaders();
+        }
+    }
+
+    fn reload_shaders(&mut self) {
+        let read = |path: &str| {
+            let mut source = String::new();
+            File::open(path)?.read_to_string(&mut source)?;
+            Ok(source)
+        };
+        let sources: io::Result<_> = read(draw_texture::VERTEX_SHADER_PATH)
+            .and_then(|vs| read(draw_texture::FRAGMENT_SHADER_PATH).map(|fs| (vs, fs)));
+        let (vs, fs) = match sources {
+            Ok(sources) => sources,
+            Err(e) => {
+                println!("Failed to reload shaders: {}", e);
+                return;
+            }
+        };
+
+        match self.draw_texture_program.reload(&mut self.gl_context, &vs, &fs) {
+            Ok(()) => println!("Reloaded shaders"),
+            Err(e) => println!("Failed to reload shaders: {}", e),
+        }
+    }
+
+    /// Blocks until the GPU has executed everything submitted so far.
+    pub fn finish(&mut self) {
+        self.flush();
+        unsafe { gl::Finish() };
+    }
+
+    /// Number of draw calls issued during the last frame
+    pub fn draw_calls(&self) -> u32 {
+        self.last_frame_draw_calls
+    }
+
+    /// GL objects currently alive. Objects dropped since the last `flush` still
+    /// count since they are only deleted then.
+    pub fn live_gl_objects(&self) -> GlObjectCounts {
+        self.gl_context.live_objects()
+    }
+
+    /// Loads the texture at `path`, or returns the existing handle if it is
+    /// already loaded. Every call needs a matching `unload_texture`.
+    pub fn load_texture<P: AsRef<Path>>(&mut self, path: P) -> Result<TextureHandle, ImageError> {
+        if let Some(handle) = self.resources.acquire(&path) {
+            return Ok(handle);
+        }
+
+        let texture = self.load_texture_uncached(&path)?;
+        if let Some(ref mut watcher) = self.watcher {
+            watcher.watch(&path);
+        }
+        Ok(self
+            .resources
+            .insert(texture, Some(path.as_ref().to_path_buf())))
+    }
+
+    /// Decodes an image file already in memory, e.g. embedded with
+    /// `include_bytes!`. The texture is not shared with other loads, so it
+    /// needs a single `unload_texture`.
+    pub fn load_texture_from_bytes(&mut self, bytes: &[u8]) -> Result<TextureHandle, ImageError> {
+        let image = image::GeneralImage::from_bytes(bytes)?;
+        let texture = self.texture_from_image(&image)?;
+        Ok(self.resources.insert(texture, None))
+    }
+
+    fn load_texture_uncached<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<GlTexture2D, ImageError> {
+        let image = image::GeneralImage::load(&path)?;
+        self.texture_from_image(&image)
+            .map_err(|e| e.with_path(path))
+    }
+
+    /// Uploads `image` after checking the GPU can hold it.
+    fn texture_from_image(&mut self, image: &GeneralImage) -> Result<GlTexture2D, ImageError> {
+        if image.num_bytes_per_component() != 1 {
+            return Err(ImageError::UnsupportedComponentType {
+                path: None,
+                bytes_per_component: image.num_bytes_per_component(),
+            });
+        }
+
+        let mut max = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max) };
+        let max = max as u32;
+        if image.width() > max || image.height() > max {
+            return Err(ImageError::TooLarge {
+                path: None,
+                w: image.width(),
+                h: image.height(),
+                max,
+            });
+        }
+
+        Ok(GlTexture2D::from_image(&mut self.gl_context, image))
+    }
+
+    /// Gives up one reference taken by `load_texture`. Returns whether the
+    /// texture was freed.
+    pub fn unload_texture(&mut self, handle: TextureHandle) -> bool {
+        self.resources.unload(handle)
+    }
+
+    /// Frees every loaded texture not in `used`, see `Resources::unload_unused`.
+    pub fn unload_unused_textures(&mut self, used: &[TextureHandle]) -> usize {
+        self.resources.unload_unused(used)
+    }
+
+    /// Texture `handle` refers to. Panics if it has been unloaded.
+    pub fn texture(&self, handle: TextureHandle) -> &GlTexture2D {
+        self.resources
+            .texture(handle)
+            .expect("Texture handle refers to an unloaded texture")
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Uploads `image`, which uses straight alpha like image files.
+    pub fn create_texture(&mut self, image: &RgbaImage) -> GlTexture2D {
+        GlTexture2D::from_rgba_image(&mut self.gl_context, image)
+    }
+
+    /// Uploads an image of any supported pixel type, e.g. an `image::Image`
+    /// generated in code.
+    pub fn create_texture_from_image(
+        &mut self,
+        image: &GeneralImage,
+    ) -> Result<GlTexture2D, ImageError> {
+        self.texture_from_image(image)
+    }
+
+    /// Loads an atlas image together with its descriptor, see `Atlas::parse_descriptor`.
+    pub fn load_atlas<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        image_path: P,
+        descriptor_path: Q,
+    ) -> io::Result<Atlas> {
+        let mut descriptor = String::new();
+        File::open(descriptor_path)?.read_to_string(&mut descriptor)?;
+        let regions = Atlas::parse_descriptor(&descriptor)?;
+
+        let texture = self.load_texture_uncached(image_path)?;
+        Atlas::new(texture, regions)
+    }
+
+    /// Loads a BMFont text descriptor and the glyph texture it refers to,
+    /// which is looked up next to the descriptor.
+    pub fn load_font<P: AsRef<Path>>(&mut self, descriptor_path: P) -> io::Result<BitmapFont> {
+        let descriptor_path = descriptor_path.as_ref();
+        let mut descriptor = String::new();
+        File::open(descriptor_path)?.read_to_string(&mut descriptor)?;
+        let descriptor = BitmapFont::parse_descriptor(&descriptor)?;
+
+        let page_path = descriptor_path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(&descriptor.page);
+        let texture = self.load_texture_uncached(page_path)?;
+        BitmapFont::new(texture, descriptor)
+    }
+
+    /// Cache rasterizing `font` at `pixel_height`, see `draw_truetype_text`.
+    pub fn create_glyph_cache(&mut self, font: Rc<TrueTypeFont>, pixel_height: f32) -> GlyphCache {
+        GlyphCache::new(&mut self.gl_context, font, pixel_height)
+    }
+
+    /// Draws `texture` at its natural size at the bottom left corner of the canvas.
+    pub fn draw_texture<T: TextureRef>(&mut self, texture: &T) {
+        let sprite = Sprite::for_texture(texture.resolve(&self.resources));
+        self.draw_sprite(texture, &sprite);
+    }
+
+    /// Draws `texture` onto the unit quad transformed by `trans` into canvas pixels.
+    pub fn draw_texture_with_trans<T: TextureRef>(&mut self, texture: &T, trans: Trans2) {
+        self.draw_texture_ex(
+            texture,
+            &Rect::new(0.0, 0.0, 1.0, 1.0),
+            trans,
+            &Color::white(),
+        );
+    }
+
+    /// Draws `region` of `texture` onto the unit quad transformed by `trans` into canvas pixels.
+    pub fn draw_texture_region<T: TextureRef>(
+        &mut self,
+        texture: &T,
+        region: &TextureRegion,
+        trans: Trans2,
+    ) {
+        let texcoords = region.texcoords(texture.resolve(&self.resources));
+        self.draw_texture_ex(texture, &texcoords, trans, &Color::white());
+    }
+
+    pub fn draw_sprite<T: TextureRef>(&mut self, texture: &T, sprite: &Sprite) {
+        self.draw_texture_ex(
+            texture,
+            &Rect::new(0.0, 0.0, 1.0, 1.0),
+            sprite.trans(),
+            &sprite.tint,
+        );
+    }
+
+    pub fn draw_sprite_region<T: TextureRef>(
+        &mut self,
+        texture: &T,
+        region: &TextureRegion,
+        sprite: &Sprite,
+    ) {
+        let texcoords = region.texcoords(texture.resolve(&self.resources));
+        self.draw_texture_ex(texture, &texcoords, sprite.trans(), &sprite.tint);
+    }
+
+    /// Draws the `texcoords` part of `texture` onto the unit quad transformed by
+    /// `trans` into canvas pixels, multiplied by `tint`.
+    pub fn draw_texture_ex<T: TextureRef>(
+        &mut self,
+        texture: &T,
+        texcoords: &Rect,
+        trans: Trans2,
+        tint: &Color,
+    ) {
+        self.draw_texture_program.draw(
+            &mut self.gl_context,
+            texture.resolve(&self.resources),
+            trans,
+            texcoords,
+            tint,
+        );
+    }
+
+    /// Draws `text` glyph by glyph, see `Text` for how it is placed.
+    pub fn draw_text(&mut self, font: &BitmapFont, text: &Text) {
+        for (region, sprite) in font.layout(text) {
+            self.draw_sprite_region(font.texture(), &region, &sprite);
+        }
+    }
+
+    /// Rasterizes the glyphs of `text` missing from `cache`, then draws it.
+    /// Returns `false` if not all glyphs fit into the cache, the text is still
+    /// drawn but with those characters left out.
+    pub fn draw_truetype_text(&mut self, cache: &mut GlyphCache, text: &Text) -> bool {
+        let complete = cache.prepare(&mut self.gl_context, text.text);
+        self.draw_text(cache.font(), text);
+        complete
+    }
+
+    /// Draws the region called `name` of `atlas`. Returns `false` if there is no such region.
+    pub fn draw_atlas_region(&mut self, atlas: &Atlas, name: &str, trans: Trans2) -> bool {
+        match atlas.region(name) {
+            Some(region) => {
+                self.draw_texture_region(atlas.texture(), region, trans);
+                true
+            }
+            None => false,
+        }
     }
 }
 
-pub struct GlContext {}
+pub struct GlContext {
+    state: Rc<ContextState>,
+    /// Framebuffer last bound with `bind_framebuffer`
+    framebuffer: u32,
+}
 
 impl GlContext {
     pub fn new<F>(loadfn: F) -> GlContext
@@ -66,8 +577,6 @@ impl GlContext {
                     .into_owned(),
             );
 
-            gl::Viewport(0, 0, 288, 512);
-
             gl::ClearColor(0.0, 0.0, 0.0, 0.0);
 
             gl::Enable(gl::BLEND);
@@ -77,7 +586,10 @@ impl GlContext {
             gl::Enable(gl::FRAMEBUFFER_SRGB);
         }
 
-        GlContext {}
+        GlContext {
+            state: ContextState::new(),
+            framebuffer: 0,
+        }
     }
 
     pub fn is_current(&self) -> bool {
@@ -90,26 +602,90 @@ impl GlContext {
             gl::GenTextures(1, &mut texture);
         }
         GlTextureId {
-            id: texture,
+            object: self.adopt(GlObjectKind::Texture, texture),
             target: gl::TEXTURE_2D,
         }
     }
 
+    pub fn gen_framebuffer(&mut self) -> GlObject {
+        let mut fbo = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+        }
+        self.adopt(GlObjectKind::Framebuffer, fbo)
+    }
+
+    pub fn gen_buffer(&mut self) -> GlObject {
+        let mut buffer = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut buffer);
+        }
+        self.adopt(GlObjectKind::Buffer, buffer)
+    }
+
+    pub fn gen_vertex_array(&mut self) -> GlObject {
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        self.adopt(GlObjectKind::VertexArray, vao)
+    }
+
+    /// Takes ownership of `id`, created by GL calls not wrapped here like
+    /// `gl::CreateProgram`.
+    pub fn adopt(&mut self, kind: GlObjectKind, id: u32) -> GlObject {
+        GlObject::new(&self.state, kind, id)
+    }
+
+    pub fn live_objects(&self) -> GlObjectCounts {
+        self.state.live()
+    }
+
+    /// Deletes the objects dropped since the last call. Only safe once no
+    /// pending draw refers to them anymore.
+    pub fn delete_garbage(&mut self) {
+        self.state.delete_garbage();
+    }
+
+    pub fn bind_framebuffer(&mut self, fbo: u32) {
+        assert!(self.is_current());
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+        }
+        self.framebuffer = fbo;
+    }
+
+    pub fn bound_framebuffer(&self) -> u32 {
+        self.framebuffer
+    }
+
     pub fn bind_texture(&mut self, texture_id: &GlTextureId) {
         assert!(self.is_current());
         unsafe {
-            gl::BindTexture(texture_id.target, texture_id.id);
+            gl::BindTexture(texture_id.target, texture_id.id());
         }
     }
 }
 
+impl Drop for GlContext {
+    fn drop(&mut self) {
+        self.state.release();
+    }
+}
+
+/// Texture object and the target it binds to. The texture is deleted on drop.
 pub struct GlTextureId {
-    id: u32,
+    object: GlObject,
     target: u32,
 }
 
+impl GlTextureId {
+    pub fn id(&self) -> u32 {
+        self.object.id()
+    }
+}
+
 pub struct GlTexture2D {
-    ctx: *mut GlContext,
     id: GlTextureId,
     w: u32,
     h: u32,
@@ -120,6 +696,11 @@ impl GlTexture2D {
         assert!(ctx.is_current());
         assert!(image.num_bytes_per_component() == 1);
 
+        // There is no two channel sRGB format, so grey is expanded to RGB
+        if image.num_component() == 2 {
+            return GlTexture2D::from_rgba_image(ctx, &image.to_rgba());
+        }
+
         let id = ctx.gen_texture_2d();
         ctx.bind_texture(&id);
 
@@ -139,6 +720,13 @@ impl GlTexture2D {
                 internal_format = gl::SRGB8_ALPHA8;
                 format = gl::RGBA;
             }
+            3 => {
+                // Rows are padded to the default unpack alignment of 4 bytes
+                texture_stride = (w as isize * 3 + 3) / 4 * 4;
+                num_pixels = w;
+                internal_format = gl::SRGB8;
+                format = gl::RGB;
+            }
             1 => {
                 texture_stride = ((w as f32 / 4.0).ceil() * 4.0) as isize;
                 num_pixels = texture_stride as u32;
@@ -166,24 +754,16 @@ impl GlTexture2D {
         assert!(texture_stride % 4 == 0);
 
         // flip image vertically
-        let mut vec = Vec::<u8>::with_capacity(texture_stride as usize * h as usize);
-        unsafe {
-            let mut dst: *mut u8 = vec.as_mut_ptr().offset(texture_stride * (h - 1) as isize);
-            let mut src: *const u8 = bytes.as_ptr();
-
-            for _ in 0..h {
-                std::ptr::copy_nonoverlapping(src, dst, stride as usize);
-                src = src.offset(stride);
-                dst = dst.offset(-texture_stride);
+        let mut vec = vec![0; texture_stride as usize * h as usize];
+        if stride > 0 {
+            let src_rows = bytes.chunks(stride as usize).rev();
+            for (dst, src) in vec.chunks_mut(texture_stride as usize).zip(src_rows) {
+                dst[..src.len()].copy_from_slice(src);
             }
         }
 
+        set_default_parameters(&id);
         unsafe {
-            gl::TexParameteri(id.target, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
-            gl::TexParameteri(id.target, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
-            gl::TexParameteri(id.target, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
-            gl::TexParameteri(id.target, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
-
             gl::PixelStorei(gl::UNPACK_ROW_LENGTH, num_pixels as i32);
             gl::TexImage2D(
                 id.target,
@@ -198,7 +778,90 @@ impl GlTexture2D {
             );
         }
 
-        GlTexture2D { ctx, id, w, h }
+        GlTexture2D { id, w, h }
+    }
+
+    /// Texture from pixels generated at runtime
+    pub fn from_rgba_image(ctx: &mut GlContext, image: &RgbaImage) -> GlTexture2D {
+        GlTexture2D::from_image(ctx, &GeneralImage::from(image.clone()))
+    }
+
+    /// Single channel texture drawn as white with the channel as alpha, like
+    /// `A8` images. `pixels` holds `w * h` bytes, rows top to bottom.
+    pub fn from_a8(ctx: &mut GlContext, w: u32, h: u32, pixels: &[u8]) -> GlTexture2D {
+        assert!(pixels.len() == w as usize * h as usize);
+
+        let image = GeneralImage::from_vec(w, h, 1, pixels.to_vec()).expect("A8 has 1 channel");
+        GlTexture2D::from_image(ctx, &image)
+    }
+
+    /// Overwrites `region` of a texture created by `from_a8`. `pixels` holds
+    /// `region.w * region.h` bytes, rows top to bottom.
+    pub fn update_a8(&mut self, ctx: &mut GlContext, region: &TextureRegion, pixels: &[u8]) {
+        assert!(ctx.is_current());
+        assert!(region.x + region.w <= self.w && region.y + region.h <= self.h);
+        assert!(pixels.len() == region.w as usize * region.h as usize);
+        if pixels.is_empty() {
+            return;
+        }
+
+        ctx.bind_texture(&self.id);
+
+        // flip image vertically
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for row in pixels.chunks(region.w as usize).rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage2D(
+                self.id.target,
+                0,
+                region.x as i32,
+                (self.h - region.y - region.h) as i32,
+                region.w as i32,
+                region.h as i32,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                flipped.as_ptr() as *const c_void,
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+    }
+
+    /// Texture with uninitialized contents, e.g. to render into
+    pub fn empty(ctx: &mut GlContext, w: u32, h: u32) -> GlTexture2D {
+        assert!(ctx.is_current());
+
+        let id = ctx.gen_texture_2d();
+        ctx.bind_texture(&id);
+
+        set_default_parameters(&id);
+        unsafe {
+            gl::TexImage2D(
+                id.target,
+                0,
+                gl::SRGB8_ALPHA8 as i32,
+                w as i32,
+                h as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+        }
+
+        GlTexture2D { id, w, h }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.w
+    }
+
+    pub fn height(&self) -> u32 {
+        self.h
     }
 
     // fn set_parameteri(&mut self, name: u32, value: i32) {
@@ -222,3 +885,14 @@ impl GlTexture2D {
     //     self.bind_texture(texture_id);
     // }
 }
+
+/// Nearest filtering and clamping for the texture bound to `id.target`, all
+/// textures are drawn pixel exact.
+fn set_default_parameters(id: &GlTextureId) {
+    unsafe {
+        gl::TexParameteri(id.target, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+        gl::TexParameteri(id.target, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        gl::TexParameteri(id.target, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(id.target, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+    }
+}
@@ -0,0 +1,140 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use super::gl;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlObjectKind {
+    Texture,
+    Buffer,
+    VertexArray,
+    Program,
+    Framebuffer,
+}
+
+/// Number of GL objects alive per kind, to make leaks visible
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GlObjectCounts {
+    pub textures: usize,
+    pub buffers: usize,
+    pub vertex_arrays: usize,
+    pub programs: usize,
+    pub framebuffers: usize,
+}
+
+impl GlObjectCounts {
+    pub fn total(&self) -> usize {
+        self.textures + self.buffers + self.vertex_arrays + self.programs + self.framebuffers
+    }
+
+    fn get_mut(&mut self, kind: GlObjectKind) -> &mut usize {
+        match kind {
+            GlObjectKind::Texture => &mut self.textures,
+            GlObjectKind::Buffer => &mut self.buffers,
+            GlObjectKind::VertexArray => &mut self.vertex_arrays,
+            GlObjectKind::Program => &mut self.programs,
+            GlObjectKind::Framebuffer => &mut self.framebuffers,
+        }
+    }
+}
+
+/// Bookkeeping shared between a `GlContext` and every object created with it
+pub struct ContextState {
+    live: Cell<GlObjectCounts>,
+    /// Objects dropped since the last `delete_garbage`. They may still be
+    /// referenced by batched draws, so deleting them waits for the next flush.
+    garbage: RefCell<Vec<(GlObjectKind, u32)>>,
+    /// Set when the context goes away, objects dropped later are deleted
+    /// right away
+    released: Cell<bool>,
+}
+
+impl ContextState {
+    pub fn new() -> Rc<ContextState> {
+        Rc::new(ContextState {
+            live: Cell::new(GlObjectCounts::default()),
+            garbage: RefCell::new(Vec::new()),
+            released: Cell::new(false),
+        })
+    }
+
+    pub fn live(&self) -> GlObjectCounts {
+        self.live.get()
+    }
+
+    /// Deletes everything dropped since the last call.
+    pub fn delete_garbage(&self) {
+        let garbage = self.garbage.replace(Vec::new());
+        for (kind, id) in garbage {
+            self.delete(kind, id);
+        }
+    }
+
+    pub fn release(&self) {
+        self.delete_garbage();
+        self.released.set(true);
+    }
+
+    fn add(&self, kind: GlObjectKind) {
+        let mut live = self.live.get();
+        *live.get_mut(kind) += 1;
+        self.live.set(live);
+    }
+
+    fn delete(&self, kind: GlObjectKind, id: u32) {
+        unsafe {
+            match kind {
+                GlObjectKind::Texture => gl::DeleteTextures(1, &id),
+                GlObjectKind::Buffer => gl::DeleteBuffers(1, &id),
+                GlObjectKind::VertexArray => gl::DeleteVertexArrays(1, &id),
+                GlObjectKind::Program => gl::DeleteProgram(id),
+                GlObjectKind::Framebuffer => gl::DeleteFramebuffers(1, &id),
+            }
+        }
+
+        let mut live = self.live.get();
+        *live.get_mut(kind) -= 1;
+        self.live.set(live);
+    }
+}
+
+/// Owned name of a GL object, deleted when dropped.
+///
+/// Objects keep the state of the context that created them alive, so they can
+/// safely be dropped in any order relative to `Graphics`. The GL context itself
+/// still has to be current when that happens.
+pub struct GlObject {
+    kind: GlObjectKind,
+    id: u32,
+    state: Rc<ContextState>,
+}
+
+impl GlObject {
+    /// Takes ownership of `id`, which has to be a freshly created object of `kind`.
+    pub fn new(state: &Rc<ContextState>, kind: GlObjectKind, id: u32) -> GlObject {
+        state.add(kind);
+        GlObject {
+            kind,
+            id,
+            state: state.clone(),
+        }
+    }
+
+    pub fn kind(&self) -> GlObjectKind {
+        self.kind
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Drop for GlObject {
+    fn drop(&mut self) {
+        if self.state.released.get() {
+            self.state.delete(self.kind, self.id);
+        } else {
+            self.state.garbage.borrow_mut().push((self.kind, self.id));
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#version 330 core
+
+uniform sampler2D texture0;
+
+in vec2 v_texcoord;
+in vec4 v_color;
+
+out vec4 frag_color;
+
+void main() {
+    vec4 tex_color = texture(texture0, v_texcoord);
+    // Pre-multiply alpha
+    tex_color = vec4(tex_color.rgb * tex_color.a, tex_color.a);
+
+    frag_color = tex_color * v_color;
+}
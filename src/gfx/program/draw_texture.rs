@@ -2,197 +2,237 @@ use std;
 use std::mem;
 use std::os::raw::c_void;
 
-use math::{GlMat3, Trans2};
-use super::super::{gl, GlContext, GlTexture2D};
-use super::compile_program;
-
+use math::{GlMat3, Rect, Trans2};
+use super::super::{gl, Color, GlContext, GlObject, GlTexture2D};
+use super::{Program, Sampler, ShaderError, Vertex, VertexAttribute};
+
+/// Initial number of quads the vertex buffer can hold before it has to grow
+const INITIAL_QUAD_CAPACITY: usize = 64;
+
+/// Draws textured quads.
+///
+/// Quads are accumulated into one vertex buffer and only submitted when the
+/// texture changes or `flush` is called, so consecutive sprites from the same
+/// texture cost a single draw call.
 pub struct DrawTextureProgram {
-    vao: u32,
-    vbo: u32,
-    ebo: u32,
-    program: u32,
-    loc_mvp: i32,
+    vao: GlObject,
+    vbo: GlObject,
+    ebo: GlObject,
+    program: Program,
+    mvp: Trans2,
+    /// Number of quads the GPU buffers currently have room for
+    quad_capacity: usize,
+    vertices: Vec<DrawTextureVertexAttrib>,
+    texture: Option<u32>,
+    num_draw_calls: u32,
 }
 
 impl DrawTextureProgram {
-    pub fn new(ctx: &mut GlContext) -> DrawTextureProgram {
-        let program = compile_program(ctx, VERTEX_SHADER, FRAGMENT_SHADER);
-        let mut vao = 0;
-        let mut vbo = 0;
-        let mut ebo = 0;
-        let loc_mvp;
-        unsafe {
-            gl::GenVertexArrays(1, &mut vao);
-            gl::GenBuffers(1, &mut vbo);
-            gl::GenBuffers(1, &mut ebo);
+    pub fn new(ctx: &mut GlContext) -> Result<DrawTextureProgram, ShaderError> {
+        let program = Program::new(ctx, VERTEX_SHADER, FRAGMENT_SHADER)?;
+        program.set_uniform("texture0", &Sampler(0));
 
-            gl::BindVertexArray(vao);
+        let vao = ctx.gen_vertex_array();
+        let vbo = ctx.gen_buffer();
+        let ebo = ctx.gen_buffer();
+        unsafe {
+            gl::BindVertexArray(vao.id());
 
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo.id());
             gl::BufferData(gl::ARRAY_BUFFER, 0, std::ptr::null(), gl::STREAM_DRAW);
 
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo.id());
             gl::BufferData(
                 gl::ELEMENT_ARRAY_BUFFER,
                 0,
                 std::ptr::null(),
                 gl::STREAM_DRAW,
             );
-
-            // t0
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<DrawTextureVertexAttrib>() as i32,
-                0 as *const c_void,
-            );
-            gl::EnableVertexAttribArray(0);
-
-            // t1
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<DrawTextureVertexAttrib>() as i32,
-                mem::size_of::<[f32; 3]> as *const c_void,
-            );
-            gl::EnableVertexAttribArray(1);
-
-            // t2
-            gl::VertexAttribPointer(
-                2,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<DrawTextureVertexAttrib>() as i32,
-                mem::size_of::<[f32; 6]> as *const c_void,
-            );
-            gl::EnableVertexAttribArray(2);
-
-            // pos
-            gl::VertexAttribPointer(
-                3,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<DrawTextureVertexAttrib>() as i32,
-                mem::size_of::<[f32; 9]> as *const c_void,
-            );
-            gl::EnableVertexAttribArray(3);
-
-            // texcoord
-            gl::VertexAttribPointer(
-                4,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<DrawTextureVertexAttrib>() as i32,
-                mem::size_of::<[f32; 11]> as *const c_void,
-            );
-            gl::EnableVertexAttribArray(4);
-
-            // color
-            gl::VertexAttribPointer(
-                5,
-                4,
-                gl::FLOAT,
-                gl::FALSE,
-                mem::size_of::<DrawTextureVertexAttrib>() as i32,
-                mem::size_of::<[f32; 13]> as *const c_void,
-            );
-            gl::EnableVertexAttribArray(5);
+            program.set_vertex_layout::<DrawTextureVertexAttrib>();
 
             gl::BindVertexArray(0);
-
-            gl::UseProgram(program);
-            gl::Uniform1i(
-                gl::GetUniformLocation(program, "texture0".as_ptr() as *const i8),
-                0,
-            );
-            loc_mvp = gl::GetUniformLocation(program, "MVP".as_ptr() as *const i8);
         }
 
-        DrawTextureProgram {
+        let mut draw_texture_program = DrawTextureProgram {
             vao,
             vbo,
             ebo,
             program,
-            loc_mvp,
+            mvp: Trans2::identity(),
+            quad_capacity: 0,
+            vertices: Vec::with_capacity(INITIAL_QUAD_CAPACITY * 4),
+            texture: None,
+            num_draw_calls: 0,
+        };
+        draw_texture_program.reserve(ctx, INITIAL_QUAD_CAPACITY);
+        Ok(draw_texture_program)
+    }
+
+    /// Replaces the program with one compiled from the given sources. The
+    /// current program stays in use if they don't compile.
+    pub fn reload(
+        &mut self,
+        ctx: &mut GlContext,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> Result<(), ShaderError> {
+        let program = Program::new(ctx, vertex_shader, fragment_shader)?;
+        program.set_uniform("texture0", &Sampler(0));
+
+        // Queued quads belong to the old program
+        self.flush(ctx);
+        unsafe {
+            gl::BindVertexArray(self.vao.id());
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo.id());
+            // Inputs may have moved to other locations
+            program.set_vertex_layout::<DrawTextureVertexAttrib>();
+            gl::BindVertexArray(0);
         }
+        self.program = program;
+        Ok(())
     }
 
-    pub fn draw(&mut self, ctx: &mut GlContext, texture: &GlTexture2D, trans: Trans2) {
+    /// Queues a unit quad transformed by `trans`, textured with the `texcoords`
+    /// part of `texture` and multiplied by `color`. The quad is drawn on the next flush.
+    pub fn draw(
+        &mut self,
+        ctx: &mut GlContext,
+        texture: &GlTexture2D,
+        trans: Trans2,
+        texcoords: &Rect,
+        color: &Color,
+    ) {
         assert!(ctx.is_current());
 
+        if self.texture != Some(texture.id.id()) {
+            self.flush(ctx);
+            self.texture = Some(texture.id.id());
+        }
+
         let glm = GlMat3::from(trans);
-        let vertices = [
-            DrawTextureVertexAttrib {
-                t0: [glm.e[0], glm.e[1], glm.e[2]],
-                t1: [glm.e[3], glm.e[4], glm.e[5]],
-                t2: [glm.e[6], glm.e[7], glm.e[8]],
-                pos: [1.0, 1.0],
-                texcoord: [1.0, 1.0],
-                color: [1.0, 1.0, 1.0, 1.0],
-            },
-            DrawTextureVertexAttrib {
-                t0: [glm.e[0], glm.e[1], glm.e[2]],
-                t1: [glm.e[3], glm.e[4], glm.e[5]],
-                t2: [glm.e[6], glm.e[7], glm.e[8]],
-                pos: [1.0, 0.0],
-                texcoord: [1.0, 0.0],
-                color: [1.0, 1.0, 1.0, 1.0],
-            },
-            DrawTextureVertexAttrib {
-                t0: [glm.e[0], glm.e[1], glm.e[2]],
-                t1: [glm.e[3], glm.e[4], glm.e[5]],
-                t2: [glm.e[6], glm.e[7], glm.e[8]],
-                pos: [0.0, 0.0],
-                texcoord: [0.0, 0.0],
-                color: [1.0, 1.0, 1.0, 1.0],
-            },
-            DrawTextureVertexAttrib {
-                t0: [glm.e[0], glm.e[1], glm.e[2]],
-                t1: [glm.e[3], glm.e[4], glm.e[5]],
-                t2: [glm.e[6], glm.e[7], glm.e[8]],
-                pos: [0.0, 1.0],
-                texcoord: [0.0, 1.0],
-                color: [1.0, 1.0, 1.0, 1.0],
-            },
+        let t0 = [glm.e[0], glm.e[1], glm.e[2]];
+        let t1 = [glm.e[3], glm.e[4], glm.e[5]];
+        let t2 = [glm.e[6], glm.e[7], glm.e[8]];
+        let color = color.premultiplied();
+        let u0 = texcoords.x;
+        let v0 = texcoords.y;
+        let u1 = texcoords.x + texcoords.w;
+        let v1 = texcoords.y + texcoords.h;
+        // (pos, texcoord) of each corner, matching the index pattern in `reserve`
+        let corners = [
+            ([1.0, 1.0], [u1, v1]),
+            ([1.0, 0.0], [u1, v0]),
+            ([0.0, 0.0], [u0, v0]),
+            ([0.0, 1.0], [u0, v1]),
         ];
+        for &(pos, texcoord) in corners.iter() {
+            self.vertices.push(DrawTextureVertexAttrib {
+                t0,
+                t1,
+                t2,
+                pos,
+                texcoord,
+                color,
+            });
+        }
+    }
+
+    /// Submits all queued quads in one draw call.
+    pub fn flush(&mut self, ctx: &mut GlContext) {
+        assert!(ctx.is_current());
+
+        let texture = match self.texture {
+            Some(texture) => texture,
+            None => return,
+        };
+
+        let num_quads = self.vertices.len() / 4;
+        if num_quads == 0 {
+            return;
+        }
 
-        let indices: [u32; 6] = [0, 1, 3, 1, 2, 3];
+        if num_quads > self.quad_capacity {
+            self.reserve(ctx, num_quads);
+        }
 
         unsafe {
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BindVertexArray(self.vao.id());
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo.id());
+            // Orphan the old storage so we don't stall on draws still using it
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                mem::size_of::<DrawTextureVertexAttrib>() as isize * 4,
-                vertices.as_ptr() as *const c_void,
+                (mem::size_of::<DrawTextureVertexAttrib>() * 4 * self.quad_capacity) as isize,
+                std::ptr::null(),
                 gl::STREAM_DRAW,
             );
-
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                mem::size_of::<u32>() as isize * 6,
-                indices.as_ptr() as *const c_void,
-                gl::STREAM_DRAW,
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                (mem::size_of::<DrawTextureVertexAttrib>() * self.vertices.len()) as isize,
+                self.vertices.as_ptr() as *const c_void,
             );
 
             gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindTexture(gl::TEXTURE_2D, texture.id.id);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+
+            self.program.set_uniform("MVP", &GlMat3::from(self.mvp));
+
+            gl::DrawElements(
+                gl::TRIANGLES,
+                (num_quads * 6) as i32,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
+        }
+
+        self.vertices.clear();
+        self.num_draw_calls += 1;
+    }
+
+    /// Sets the transform applied to every quad after its own transform.
+    /// Quads queued so far are flushed with the previous one.
+    pub fn set_mvp(&mut self, ctx: &mut GlContext, mvp: Trans2) {
+        self.flush(ctx);
+        self.mvp = mvp;
+    }
+
+    /// Number of draw calls issued since the last call to `reset_num_draw_calls`
+    pub fn num_draw_calls(&self) -> u32 {
+        self.num_draw_calls
+    }
+
+    pub fn reset_num_draw_calls(&mut self) {
+        self.num_draw_calls = 0;
+    }
+
+    /// Grows the GPU buffers to hold at least `num_quads` quads. The index
+    /// buffer never changes between flushes, so it is only uploaded here.
+    fn reserve(&mut self, ctx: &mut GlContext, num_quads: usize) {
+        assert!(ctx.is_current());
+
+        let quad_capacity = num_quads.next_power_of_two();
+        let mut indices = Vec::<u32>::with_capacity(quad_capacity * 6);
+        for i in 0..quad_capacity as u32 {
+            let base = i * 4;
+            indices.extend_from_slice(&[base, base + 1, base + 3, base + 1, base + 2, base + 3]);
+        }
 
-            gl::UseProgram(self.program);
-            let mvp = GlMat3::from(Trans2::identity());
-            gl::UniformMatrix3fv(self.loc_mvp, 1, gl::FALSE, mvp.e.as_ptr());
+        unsafe {
+            gl::BindVertexArray(self.vao.id());
 
-            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo.id());
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (mem::size_of::<u32>() * indices.len()) as isize,
+                indices.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
 
-            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, 0 as *const c_void);
+            gl::BindVertexArray(0);
         }
+
+        self.quad_capacity = quad_capacity;
     }
 }
 
@@ -207,40 +247,32 @@ pub struct DrawTextureVertexAttrib {
     color: [f32; 4],
 }
 
-const VERTEX_SHADER: &'static str = r#"
-#version 330 core
-
-uniform mat3 MVP;
-
-layout (location = 0) in mat3 a_transform;
-layout (location = 3) in vec2 a_pos;
-layout (location = 4) in vec2 a_texcord;
-layout (location = 5) in vec4 a_color;
-
-out vec2 v_texcoord;
-out vec4 v_color;
-
-void main() {
-    gl_Position = vec4(MVP * a_transform * vec3(a_pos, 1), 1);
-    v_texcoord = a_texcord;
-    v_color = a_color;
-}"#;
-
-const FRAGMENT_SHADER: &'static str = r#"
-#version 330 core
-
-uniform sampler2D texture0;
-
-in vec2 v_texcoord;
-in vec4 v_color;
-
-out vec4 frag_color;
-
-void main() {
-    vec4 tex_color = texture(texture0, v_texcoord);
-    // Pre-multiply alpha
-    tex_color = vec4(tex_color.rgb * tex_color.a, tex_color.a);
-
-    frag_color = tex_color * v_color;
+impl Vertex for DrawTextureVertexAttrib {
+    const ATTRIBUTES: &'static [VertexAttribute] = &[
+        VertexAttribute::mat3("a_transform", mem::offset_of!(DrawTextureVertexAttrib, t0)),
+        VertexAttribute::vec("a_pos", 2, mem::offset_of!(DrawTextureVertexAttrib, pos)),
+        VertexAttribute::vec(
+            "a_texcord",
+            2,
+            mem::offset_of!(DrawTextureVertexAttrib, texcoord),
+        ),
+        VertexAttribute::vec(
+            "a_color",
+            4,
+            mem::offset_of!(DrawTextureVertexAttrib, color),
+        ),
+    ];
 }
-"#;
+
+/// Shader sources are built in, the paths are only used for hot reloading
+pub const VERTEX_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/gfx/program/draw_texture.vert"
+);
+pub const FRAGMENT_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/gfx/program/draw_texture.frag"
+);
+
+const VERTEX_SHADER: &str = include_str!("draw_texture.vert");
+const FRAGMENT_SHADER: &str = include_str!("draw_texture.frag");
@@ -0,0 +1,17 @@
+#version 330 core
+
+uniform mat3 MVP;
+
+layout (location = 0) in mat3 a_transform;
+layout (location = 3) in vec2 a_pos;
+layout (location = 4) in vec2 a_texcord;
+layout (location = 5) in vec4 a_color;
+
+out vec2 v_texcoord;
+out vec4 v_color;
+
+void main() {
+    gl_Position = vec4(MVP * a_transform * vec3(a_pos, 1), 1);
+    v_texcoord = a_texcord;
+    v_color = a_color;
+}
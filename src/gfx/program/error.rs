@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::fmt;
+
+use super::super::gl;
+
+/// Lines of source shown before and after each line the log complains about
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+impl ShaderStage {
+    pub fn gl_type(&self) -> u32 {
+        match *self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Compile {
+        stage: ShaderStage,
+        /// Complete info log of the driver
+        log: String,
+        source: String,
+    },
+    Link {
+        log: String,
+    },
+}
+
+impl ShaderError {
+    /// Stage that failed to compile, `None` for link errors
+    pub fn stage(&self) -> Option<ShaderStage> {
+        match *self {
+            ShaderError::Compile { stage, .. } => Some(stage),
+            ShaderError::Link { .. } => None,
+        }
+    }
+
+    pub fn log(&self) -> &str {
+        match *self {
+            ShaderError::Compile { ref log, .. } | ShaderError::Link { ref log } => log,
+        }
+    }
+
+    /// Source lines around the lines mentioned in the log, numbered and with
+    /// those lines marked. The whole source if the log mentions no lines.
+    pub fn annotated_source(&self) -> Option<String> {
+        match *self {
+            ShaderError::Compile {
+                ref log,
+                ref source,
+                ..
+            } => Some(annotate(source, log)),
+            ShaderError::Link { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShaderError::Compile { stage, ref log, .. } => {
+                writeln!(f, "Failed to compile {} shader:", stage)?;
+                writeln!(f, "{}", log.trim_end())?;
+                write!(f, "{}", self.annotated_source().unwrap_or_default())
+            }
+            ShaderError::Link { ref log } => {
+                write!(f, "Failed to link program:\n{}", log.trim_end())
+            }
+        }
+    }
+}
+
+impl Error for ShaderError {
+    fn description(&self) -> &str {
+        match *self {
+            ShaderError::Compile { .. } => "failed to compile shader",
+            ShaderError::Link { .. } => "failed to link program",
+        }
+    }
+}
+
+fn annotate(source: &str, log: &str) -> String {
+    let lines = source.lines().collect::<Vec<_>>();
+    let error_lines = log.lines().filter_map(error_line).collect::<Vec<_>>();
+    let is_shown = |number: usize| {
+        error_lines.is_empty()
+            || error_lines
+                .iter()
+                .any(|&e| number + CONTEXT_LINES >= e && number <= e + CONTEXT_LINES)
+    };
+
+    let width = lines.len().to_string().len();
+    let mut annotated = String::new();
+    let mut skipped = false;
+    for (i, line) in lines.iter().enumerate() {
+        let number = i + 1;
+        if !is_shown(number) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            annotated.push_str("...\n");
+            skipped = false;
+        }
+
+        let marker = if error_lines.contains(&number) {
+            ">"
+        } else {
+            " "
+        };
+        annotated.push_str(&format!(
+            "{} {:>width$} | {}\n",
+            marker,
+            number,
+            line,
+            width = width
+        ));
+    }
+    annotated
+}
+
+/// Source line a driver message refers to. Drivers disagree on the format:
+///
+///     0:12(5): error: ...    Mesa
+///     ERROR: 0:12: ...       AMD, Intel on Windows
+///     0(12) : error ...      Nvidia
+fn error_line(message: &str) -> Option<usize> {
+    let bytes = message.as_bytes();
+    for (i, _) in message.match_indices(&[':', '('][..]) {
+        // Preceded by the source string index
+        if i == 0 || !bytes[i - 1].is_ascii_digit() {
+            continue;
+        }
+
+        let rest = &message[i + 1..];
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            continue;
+        }
+        match rest[digits..].chars().next() {
+            Some(':') | Some('(') | Some(')') => return rest[..digits].parse().ok(),
+            _ => continue,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::error_line;
+
+    #[test]
+    fn mesa_line() {
+        let message = "0:12(5): error: `color' undeclared";
+        assert_eq!(error_line(message), Some(12));
+    }
+
+    #[test]
+    fn amd_line() {
+        let message = "ERROR: 0:7: 'vec5' : no matching overloaded function found";
+        assert_eq!(error_line(message), Some(7));
+    }
+
+    #[test]
+    fn nvidia_line() {
+        let message = "0(34) : error C1008: undefined variable \"color\"";
+        assert_eq!(error_line(message), Some(34));
+    }
+
+    #[test]
+    fn message_without_line() {
+        assert_eq!(error_line("error: linking failed: 2 errors"), None);
+        assert_eq!(error_line(""), None);
+    }
+}
@@ -1,44 +1,195 @@
 pub mod draw_texture;
+pub mod error;
+pub mod uniform;
+pub mod vertex;
 
-use std;
-use super::{gl, GlContext};
+use std::collections::HashMap;
+use std::ffi::CString;
 
-pub fn compile_program(ctx: &mut GlContext, vertex_shader: &str, fragment_shader: &str) -> u32 {
-    let vs = compile_shader(ctx, gl::VERTEX_SHADER, vertex_shader);
-    let fs = compile_shader(ctx, gl::FRAGMENT_SHADER, fragment_shader);
+use super::{gl, GlContext, GlObject, GlObjectKind};
+
+pub use self::error::{ShaderError, ShaderStage};
+pub use self::uniform::{Sampler, Uniform};
+pub use self::vertex::{Vertex, VertexAttribute};
+
+/// Linked shader program with the locations of its active uniforms and
+/// vertex attributes looked up once after linking.
+pub struct Program {
+    object: GlObject,
+    uniforms: HashMap<String, i32>,
+    attributes: HashMap<String, u32>,
+}
+
+impl Program {
+    pub fn new(
+        ctx: &mut GlContext,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> Result<Program, ShaderError> {
+        let object = compile_program(ctx, vertex_shader, fragment_shader)?;
+        let id = object.id();
+
+        let mut uniforms = HashMap::new();
+        for name in active_names(id, gl::ACTIVE_UNIFORMS, gl::ACTIVE_UNIFORM_MAX_LENGTH) {
+            let c_name = CString::new(name.as_bytes()).unwrap();
+            let location = unsafe { gl::GetUniformLocation(id, c_name.as_ptr()) };
+            if location >= 0 {
+                // Arrays are reported as `name[0]`, allow looking them up as `name` too
+                if name.ends_with("[0]") {
+                    uniforms.insert(name[..name.len() - 3].to_string(), location);
+                }
+                uniforms.insert(name, location);
+            }
+        }
+
+        let mut attributes = HashMap::new();
+        for name in active_names(id, gl::ACTIVE_ATTRIBUTES, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH) {
+            let c_name = CString::new(name.as_bytes()).unwrap();
+            let location = unsafe { gl::GetAttribLocation(id, c_name.as_ptr()) };
+            if location >= 0 {
+                attributes.insert(name, location as u32);
+            }
+        }
+
+        Ok(Program {
+            object,
+            uniforms,
+            attributes,
+        })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.object.id()
+    }
+
+    /// Makes this the program used by following draw calls.
+    pub fn bind(&self) {
+        unsafe { gl::UseProgram(self.id()) };
+    }
+
+    /// `None` if the shaders don't use a uniform called `name`
+    pub fn uniform_location(&self, name: &str) -> Option<i32> {
+        self.uniforms.get(name).cloned()
+    }
+
+    /// `None` if the vertex shader doesn't use an input called `name`
+    pub fn attribute_location(&self, name: &str) -> Option<u32> {
+        self.attributes.get(name).cloned()
+    }
+
+    /// Binds the program and sets uniform `name`. Returns `false` if there is
+    /// no such uniform, e.g. because the compiler removed it as unused.
+    pub fn set_uniform<U: Uniform + ?Sized>(&self, name: &str, value: &U) -> bool {
+        match self.uniform_location(name) {
+            Some(location) => {
+                self.bind();
+                unsafe { value.upload(location) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets up the bound vertex array to read vertices of type `V` from the
+    /// bound array buffer.
+    pub fn set_vertex_layout<V: Vertex>(&self) {
+        vertex::set_vertex_layout::<V>(&self.attributes);
+    }
+}
+
+/// Names of the active uniforms or attributes of `program`
+fn active_names(program: u32, count_param: u32, max_length_param: u32) -> Vec<String> {
+    let mut count = 0;
+    let mut max_length = 0;
+    unsafe {
+        gl::GetProgramiv(program, count_param, &mut count);
+        gl::GetProgramiv(program, max_length_param, &mut max_length);
+    }
+
+    let mut names = Vec::with_capacity(count as usize);
+    let mut buf = vec![0u8; max_length.max(1) as usize];
+    for index in 0..count as u32 {
+        let mut len = 0;
+        let mut size = 0;
+        let mut type_ = 0;
+        unsafe {
+            let get_active = if count_param == gl::ACTIVE_UNIFORMS {
+                gl::GetActiveUniform
+            } else {
+                gl::GetActiveAttrib
+            };
+            get_active(
+                program,
+                index,
+                buf.len() as i32,
+                &mut len,
+                &mut size,
+                &mut type_,
+                buf.as_mut_ptr() as *mut i8,
+            );
+        }
+        names.push(String::from_utf8_lossy(&buf[..len as usize]).into_owned());
+    }
+    names
+}
+
+pub fn compile_program(
+    ctx: &mut GlContext,
+    vertex_shader: &str,
+    fragment_shader: &str,
+) -> Result<GlObject, ShaderError> {
+    let vs = compile_shader(ctx, ShaderStage::Vertex, vertex_shader)?;
+    let fs = match compile_shader(ctx, ShaderStage::Fragment, fragment_shader) {
+        Ok(fs) => fs,
+        Err(e) => {
+            unsafe { gl::DeleteShader(vs) };
+            return Err(e);
+        }
+    };
 
     unsafe {
-        let program = gl::CreateProgram();
-        gl::AttachShader(program, vs);
-        gl::AttachShader(program, fs);
-        gl::LinkProgram(program);
+        let program = ctx.adopt(GlObjectKind::Program, gl::CreateProgram());
+        let program_id = program.id();
+        gl::AttachShader(program_id, vs);
+        gl::AttachShader(program_id, fs);
+        gl::LinkProgram(program_id);
+
+        // The program keeps what it needs from the shaders
+        gl::DetachShader(program_id, vs);
+        gl::DetachShader(program_id, fs);
+        gl::DeleteShader(vs);
+        gl::DeleteShader(fs);
 
         let mut is_success = 0;
-        gl::GetProgramiv(program, gl::LINK_STATUS, &mut is_success);
+        gl::GetProgramiv(program_id, gl::LINK_STATUS, &mut is_success);
         if is_success != gl::TRUE as i32 {
-            let mut buf = Vec::<u8>::with_capacity(512);
             let mut len = 0;
+            gl::GetProgramiv(program_id, gl::INFO_LOG_LENGTH, &mut len);
+            let mut buf = vec![0u8; len.max(1) as usize];
             gl::GetProgramInfoLog(
-                program,
-                buf.capacity() as i32,
+                program_id,
+                buf.len() as i32,
                 &mut len,
                 buf.as_mut_ptr() as *mut i8,
             );
-            buf.set_len(len as usize);
-            panic!(
-                "Failed to link program: {}",
-                String::from_utf8(buf).unwrap()
-            );
+            buf.truncate(len as usize);
+            return Err(ShaderError::Link {
+                log: String::from_utf8_lossy(&buf).into_owned(),
+            });
         }
 
-        program
+        Ok(program)
     }
 }
 
-pub fn compile_shader(ctx: &mut GlContext, type_: u32, source: &str) -> u32 {
+pub fn compile_shader(
+    ctx: &mut GlContext,
+    stage: ShaderStage,
+    source: &str,
+) -> Result<u32, ShaderError> {
     assert!(ctx.is_current());
     unsafe {
-        let shader = gl::CreateShader(type_);
+        let shader = gl::CreateShader(stage.gl_type());
         gl::ShaderSource(
             shader,
             1,
@@ -50,21 +201,24 @@ pub fn compile_shader(ctx: &mut GlContext, type_: u32, source: &str) -> u32 {
         let mut is_success = 0;
         gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut is_success);
         if is_success != gl::TRUE as i32 {
-            let mut buf = Vec::<u8>::with_capacity(512);
             let mut len = 0;
+            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+            let mut buf = vec![0u8; len.max(1) as usize];
             gl::GetShaderInfoLog(
                 shader,
-                buf.capacity() as i32,
+                buf.len() as i32,
                 &mut len,
                 buf.as_mut_ptr() as *mut i8,
             );
-            buf.set_len(len as usize);
-            panic!(
-                "Failed to compile shader: {}",
-                String::from_utf8(buf).unwrap()
-            );
+            buf.truncate(len as usize);
+            gl::DeleteShader(shader);
+            return Err(ShaderError::Compile {
+                stage,
+                log: String::from_utf8_lossy(&buf).into_owned(),
+                source: source.to_string(),
+            });
         }
 
-        shader
+        Ok(shader)
     }
 }
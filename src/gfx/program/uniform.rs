@@ -0,0 +1,66 @@
+use math::{GlMat3, Vec2};
+
+use super::super::gl;
+
+/// Value that can be uploaded to a uniform of the matching GLSL type.
+pub trait Uniform {
+    /// Uploads `self` to `location` of the program in use.
+    ///
+    /// # Safety
+    ///
+    /// A GL context has to be current and `location` has to belong to a
+    /// uniform of the matching type in the bound program.
+    unsafe fn upload(&self, location: i32);
+}
+
+/// Texture unit a `sampler2D` uniform reads from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sampler(pub i32);
+
+impl Uniform for f32 {
+    unsafe fn upload(&self, location: i32) {
+        gl::Uniform1f(location, *self);
+    }
+}
+
+impl Uniform for i32 {
+    unsafe fn upload(&self, location: i32) {
+        gl::Uniform1i(location, *self);
+    }
+}
+
+impl Uniform for Sampler {
+    unsafe fn upload(&self, location: i32) {
+        gl::Uniform1i(location, self.0);
+    }
+}
+
+impl Uniform for Vec2 {
+    unsafe fn upload(&self, location: i32) {
+        gl::Uniform2f(location, self.x, self.y);
+    }
+}
+
+impl Uniform for [f32; 2] {
+    unsafe fn upload(&self, location: i32) {
+        gl::Uniform2fv(location, 1, self.as_ptr());
+    }
+}
+
+impl Uniform for [f32; 3] {
+    unsafe fn upload(&self, location: i32) {
+        gl::Uniform3fv(location, 1, self.as_ptr());
+    }
+}
+
+impl Uniform for [f32; 4] {
+    unsafe fn upload(&self, location: i32) {
+        gl::Uniform4fv(location, 1, self.as_ptr());
+    }
+}
+
+impl Uniform for GlMat3 {
+    unsafe fn upload(&self, location: i32) {
+        gl::UniformMatrix3fv(location, 1, gl::FALSE, self.e.as_ptr());
+    }
+}
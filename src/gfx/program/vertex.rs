@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::mem;
+use std::os::raw::c_void;
+
+use super::super::gl;
+
+/// One input of a vertex shader and where it is stored in the vertex struct.
+/// Only `f32` components are supported.
+#[derive(Clone, Copy, Debug)]
+pub struct VertexAttribute {
+    /// Name of the `in` variable in the vertex shader
+    pub name: &'static str,
+    /// Components per column, e.g. 2 for `vec2` and 3 for `mat3`
+    pub components: i32,
+    /// 1 for vectors, the number of columns for matrices
+    pub columns: u32,
+    /// Byte offset in the vertex struct
+    pub offset: usize,
+}
+
+impl VertexAttribute {
+    /// `float` for 1 component, `vecN` for `N`
+    pub const fn vec(name: &'static str, components: i32, offset: usize) -> VertexAttribute {
+        VertexAttribute {
+            name,
+            components,
+            columns: 1,
+            offset,
+        }
+    }
+
+    /// `mat3` stored as three consecutive `[f32; 3]` columns
+    pub const fn mat3(name: &'static str, offset: usize) -> VertexAttribute {
+        VertexAttribute {
+            name,
+            components: 3,
+            columns: 3,
+            offset,
+        }
+    }
+}
+
+/// `#[repr(C)]` struct that is uploaded to a vertex buffer as is.
+///
+///     impl Vertex for MyVertex {
+///         const ATTRIBUTES: &'static [VertexAttribute] = &[
+///             VertexAttribute::vec("a_pos", 2, mem::offset_of!(MyVertex, pos)),
+///             VertexAttribute::vec("a_color", 4, mem::offset_of!(MyVertex, color)),
+///         ];
+///     }
+pub trait Vertex: Sized {
+    const ATTRIBUTES: &'static [VertexAttribute];
+}
+
+/// Points the attributes of the bound vertex array at the bound array buffer,
+/// laid out like `V`. Attributes missing from `locations`, e.g. because the
+/// shader doesn't use them, are skipped.
+pub fn set_vertex_layout<V: Vertex>(locations: &HashMap<String, u32>) {
+    let stride = mem::size_of::<V>() as i32;
+    for attribute in V::ATTRIBUTES {
+        let location = match locations.get(attribute.name) {
+            Some(&location) => location,
+            None => continue,
+        };
+
+        let column_size = attribute.components as usize * mem::size_of::<f32>();
+        for column in 0..attribute.columns {
+            let offset = attribute.offset + column as usize * column_size;
+            unsafe {
+                gl::VertexAttribPointer(
+                    location + column,
+                    attribute.components,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    offset as *const c_void,
+                );
+                gl::EnableVertexAttribArray(location + column);
+            }
+        }
+    }
+}
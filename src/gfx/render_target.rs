@@ -0,0 +1,60 @@
+use super::{gl, GlContext, GlObject, GlTexture2D};
+
+/// Offscreen framebuffer with a color texture attached.
+///
+/// While bound with `Graphics::set_render_target`, all draws end up in
+/// `texture()`, which can then be drawn like any other texture. Its contents
+/// are already in pre-multiplied alpha, so only opaque targets come out exactly
+/// the same when drawn again.
+pub struct RenderTarget {
+    fbo: GlObject,
+    texture: GlTexture2D,
+}
+
+impl RenderTarget {
+    pub fn new(ctx: &mut GlContext, w: u32, h: u32) -> RenderTarget {
+        assert!(ctx.is_current());
+
+        let previous_fbo = ctx.bound_framebuffer();
+        let texture = GlTexture2D::empty(ctx, w, h);
+        let fbo = ctx.gen_framebuffer();
+        ctx.bind_framebuffer(fbo.id());
+
+        unsafe {
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                texture.id.target,
+                texture.id.id(),
+                0,
+            );
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            assert!(
+                status == gl::FRAMEBUFFER_COMPLETE,
+                "Framebuffer is incomplete: 0x{:x}",
+                status
+            );
+        }
+
+        ctx.bind_framebuffer(previous_fbo);
+
+        RenderTarget { fbo, texture }
+    }
+
+    pub fn texture(&self) -> &GlTexture2D {
+        &self.texture
+    }
+
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    pub(super) fn fbo(&self) -> u32 {
+        self.fbo.id()
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::mem;
+use std::path::{Component, Path, PathBuf};
+
+use super::GlTexture2D;
+
+/// Lightweight reference to a texture owned by `Resources`.
+///
+/// Handles are plain values and can be copied around freely. A handle whose
+/// texture has been unloaded stays invalid, even if its slot gets reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle {
+    index: u32,
+    generation: u32,
+}
+
+struct Entry {
+    texture: GlTexture2D,
+    /// Where the texture was loaded from, if it came from a file
+    path: Option<PathBuf>,
+    /// Number of outstanding `load`s not matched by an `unload`
+    refs: u32,
+}
+
+struct Slot {
+    generation: u32,
+    entry: Option<Entry>,
+}
+
+/// Owns textures and hands out handles to them.
+///
+/// Loading the same path twice returns the same handle and only bumps its
+/// reference count. Paths are compared after `normalize`, so `./a.png` and
+/// `a.png` are the same file. The texture is freed once it has been unloaded as many
+/// times as it was loaded, or by `unload_unused`.
+pub struct Resources {
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+    by_path: HashMap<PathBuf, TextureHandle>,
+}
+
+impl Resources {
+    pub fn new() -> Resources {
+        Resources {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            by_path: HashMap::new(),
+        }
+    }
+
+    /// Handle of the texture loaded from `path`, taking another reference to it.
+    pub fn acquire<P: AsRef<Path>>(&mut self, path: P) -> Option<TextureHandle> {
+        let handle = *self.by_path.get(&normalize(path.as_ref()))?;
+        self.entry_mut(handle)?.refs += 1;
+        Some(handle)
+    }
+
+    /// Takes ownership of `texture` with a reference count of one. `path`
+    /// makes later `acquire`s with the same path find it.
+    pub fn insert(&mut self, texture: GlTexture2D, path: Option<PathBuf>) -> TextureHandle {
+        let path = path.map(|path| normalize(&path));
+        let entry = Entry {
+            texture,
+            path: path.clone(),
+            refs: 1,
+        };
+
+        let handle = match self.free_slots.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.entry = Some(entry);
+                TextureHandle {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: Some(entry),
+                });
+                TextureHandle {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+
+        if let Some(path) = path {
+            self.by_path.insert(path, handle);
+        }
+        handle
+    }
+
+    /// Handle of the texture loaded from `path` without taking a reference.
+    pub fn find<P: AsRef<Path>>(&self, path: P) -> Option<TextureHandle> {
+        self.by_path.get(&normalize(path.as_ref())).cloned()
+    }
+
+    /// Swaps in a new texture for `handle`, e.g. after its file changed.
+    /// Returns the old texture, or gives `texture` back if `handle` is unloaded.
+    pub fn replace(
+        &mut self,
+        handle: TextureHandle,
+        texture: GlTexture2D,
+    ) -> Result<GlTexture2D, GlTexture2D> {
+        match self.entry_mut(handle) {
+            Some(entry) => Ok(mem::replace(&mut entry.texture, texture)),
+            None => Err(texture),
+        }
+    }
+
+    pub fn texture(&self, handle: TextureHandle) -> Option<&GlTexture2D> {
+        let slot = self.slots.get(handle.index as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        slot.entry.as_ref().map(|entry| &entry.texture)
+    }
+
+    pub fn is_loaded(&self, handle: TextureHandle) -> bool {
+        self.texture(handle).is_some()
+    }
+
+    /// Gives up one reference to `handle`, freeing the texture when it was the
+    /// last one. Returns whether the texture was freed.
+    pub fn unload(&mut self, handle: TextureHandle) -> bool {
+        let is_last = match self.entry_mut(handle) {
+            Some(entry) => {
+                entry.refs -= 1;
+                entry.refs == 0
+            }
+            None => return false,
+        };
+
+        if is_last {
+            self.remove(handle);
+        }
+        is_last
+    }
+
+    /// Frees every texture not in `used` no matter how often it was loaded,
+    /// e.g. when switching scenes. Returns the number of textures freed.
+    pub fn unload_unused(&mut self, used: &[TextureHandle]) -> usize {
+        let unused = self
+            .handles()
+            .filter(|handle| !used.contains(handle))
+            .collect::<Vec<_>>();
+
+        for &handle in &unused {
+            self.remove(handle);
+        }
+        unused.len()
+    }
+
+    /// Handles of all loaded textures
+    pub fn handles<'a>(&'a self) -> impl Iterator<Item = TextureHandle> + 'a {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.entry.as_ref().map(|_| TextureHandle {
+                index: index as u32,
+                generation: slot.generation,
+            })
+        })
+    }
+
+    /// Paths of all textures loaded from files
+    pub fn paths<'a>(&'a self) -> impl Iterator<Item = &'a Path> + 'a {
+        self.by_path.keys().map(|path| path.as_path())
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn entry_mut(&mut self, handle: TextureHandle) -> Option<&mut Entry> {
+        let slot = self.slots.get_mut(handle.index as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        slot.entry.as_mut()
+    }
+
+    fn remove(&mut self, handle: TextureHandle) {
+        let slot = &mut self.slots[handle.index as usize];
+        let entry = match slot.entry.take() {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        // Outstanding handles must not see whatever gets this slot next
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(handle.index);
+
+        if let Some(path) = entry.path {
+            self.by_path.remove(&path);
+        }
+    }
+}
+
+/// Drops `.` components and redundant separators. `..` is kept since it can't
+/// be resolved without looking at the file system.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+impl Default for Resources {
+    fn default() -> Resources {
+        Resources::new()
+    }
+}
+
+/// Anything that can be drawn as a texture, i.e. a texture owned by the
+/// caller or a handle to one owned by `Resources`.
+pub trait TextureRef {
+    fn resolve<'a>(&'a self, resources: &'a Resources) -> &'a GlTexture2D;
+}
+
+impl TextureRef for GlTexture2D {
+    fn resolve<'a>(&'a self, _: &'a Resources) -> &'a GlTexture2D {
+        self
+    }
+}
+
+impl TextureRef for TextureHandle {
+    fn resolve<'a>(&'a self, resources: &'a Resources) -> &'a GlTexture2D {
+        resources
+            .texture(*self)
+            .expect("Texture handle refers to an unloaded texture")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::super::object::ContextState;
+    use super::super::{gl, GlObject, GlObjectKind, GlTextureId};
+    use super::*;
+
+    /// Texture that was never uploaded. Dropping it only queues its deletion,
+    /// which never happens since the state is never flushed.
+    fn texture(state: &Rc<ContextState>) -> GlTexture2D {
+        GlTexture2D {
+            id: GlTextureId {
+                object: GlObject::new(state, GlObjectKind::Texture, 1),
+                target: gl::TEXTURE_2D,
+            },
+            w: 1,
+            h: 1,
+        }
+    }
+
+    #[test]
+    fn loads_are_refcounted() {
+        let state = ContextState::new();
+        let mut resources = Resources::new();
+        let handle = resources.insert(texture(&state), Some(PathBuf::from("a.png")));
+        assert_eq!(resources.acquire("a.png"), Some(handle));
+
+        assert!(!resources.unload(handle));
+        assert!(resources.is_loaded(handle));
+        assert!(resources.unload(handle));
+        assert!(!resources.is_loaded(handle));
+        assert_eq!(resources.find("a.png"), None);
+        assert!(!resources.unload(handle));
+    }
+
+    #[test]
+    fn stale_handle_after_slot_reuse() {
+        let state = ContextState::new();
+        let mut resources = Resources::new();
+        let old = resources.insert(texture(&state), Some(PathBuf::from("a.png")));
+        resources.unload(old);
+
+        let new = resources.insert(texture(&state), Some(PathBuf::from("b.png")));
+        assert_eq!(new.index, old.index);
+        assert!(resources.is_loaded(new));
+        assert!(!resources.is_loaded(old));
+        assert!(resources.texture(old).is_none());
+        assert!(!resources.unload(old));
+        assert!(resources.replace(old, texture(&state)).is_err());
+        assert!(resources.is_loaded(new));
+    }
+
+    #[test]
+    fn unload_unused_keeps_used() {
+        let state = ContextState::new();
+        let mut resources = Resources::new();
+        let a = resources.insert(texture(&state), Some(PathBuf::from("a.png")));
+        let b = resources.insert(texture(&state), None);
+        let c = resources.insert(texture(&state), Some(PathBuf::from("c.png")));
+        resources.acquire("c.png");
+
+        assert_eq!(resources.unload_unused(&[b]), 2);
+        assert!(!resources.is_loaded(a));
+        assert!(resources.is_loaded(b));
+        assert!(!resources.is_loaded(c));
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources.paths().count(), 0);
+    }
+
+    #[test]
+    fn paths_are_normalized() {
+        let state = ContextState::new();
+        let mut resources = Resources::new();
+        let handle = resources.insert(texture(&state), Some(PathBuf::from("./sprites/a.png")));
+        assert_eq!(resources.find("sprites/a.png"), Some(handle));
+        assert_eq!(resources.acquire("sprites//./a.png"), Some(handle));
+        assert_eq!(resources.find("sprites/b.png"), None);
+        assert_eq!(
+            resources.paths().collect::<Vec<_>>(),
+            [Path::new("sprites/a.png")]
+        );
+    }
+}
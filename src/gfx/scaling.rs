@@ -0,0 +1,198 @@
+use math::Vec2;
+
+/// How the logical canvas is fit into the window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalingMode {
+    /// Largest integer scale that fits, so every canvas pixel is the same size.
+    /// Falls back to `AspectFit` when the window is smaller than the canvas.
+    PixelPerfect,
+    /// Largest scale that keeps the aspect ratio, bars fill the rest
+    AspectFit,
+    /// Fill the whole window, distorting the aspect ratio
+    Stretch,
+}
+
+impl ScalingMode {
+    /// The mode after this one, for cycling through all modes
+    pub fn next(&self) -> ScalingMode {
+        match *self {
+            ScalingMode::PixelPerfect => ScalingMode::AspectFit,
+            ScalingMode::AspectFit => ScalingMode::Stretch,
+            ScalingMode::Stretch => ScalingMode::PixelPerfect,
+        }
+    }
+}
+
+/// Area of the window the canvas is rendered into, in window pixels with the
+/// origin at the bottom left like `gl::Viewport`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// Fits a `canvas_w` x `canvas_h` canvas into a window and maps window
+/// coordinates back onto the canvas.
+#[derive(Clone, Debug)]
+pub struct Scaler {
+    mode: ScalingMode,
+    canvas_w: u32,
+    canvas_h: u32,
+    window_w: u32,
+    window_h: u32,
+    viewport: Viewport,
+}
+
+impl Scaler {
+    pub fn new(mode: ScalingMode, canvas_w: u32, canvas_h: u32) -> Scaler {
+        let mut scaler = Scaler {
+            mode,
+            canvas_w,
+            canvas_h,
+            window_w: canvas_w,
+            window_h: canvas_h,
+            viewport: Viewport {
+                x: 0,
+                y: 0,
+                w: canvas_w,
+                h: canvas_h,
+            },
+        };
+        scaler.update();
+        scaler
+    }
+
+    pub fn mode(&self) -> ScalingMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ScalingMode) {
+        self.mode = mode;
+        self.update();
+    }
+
+    pub fn set_window_size(&mut self, window_w: u32, window_h: u32) {
+        self.window_w = window_w;
+        self.window_h = window_h;
+        self.update();
+    }
+
+    pub fn window_size(&self) -> (u32, u32) {
+        (self.window_w, self.window_h)
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// Maps a window position, origin at the top left as reported by window
+    /// events, to canvas pixels with the origin at the bottom left.
+    ///
+    /// Returns `None` if the position is on the bars outside of the canvas.
+    pub fn window_to_canvas(&self, x: f64, y: f64) -> Option<Vec2> {
+        let viewport = &self.viewport;
+        if viewport.w == 0 || viewport.h == 0 {
+            return None;
+        }
+
+        let vx = x - viewport.x as f64;
+        let vy = (self.window_h as f64 - y) - viewport.y as f64;
+        if vx < 0.0 || vy < 0.0 || vx >= viewport.w as f64 || vy >= viewport.h as f64 {
+            return None;
+        }
+
+        Some(Vec2::new(
+            (vx * self.canvas_w as f64 / viewport.w as f64) as f32,
+            (vy * self.canvas_h as f64 / viewport.h as f64) as f32,
+        ))
+    }
+
+    fn update(&mut self) {
+        let (w, h) = match self.mode {
+            ScalingMode::Stretch => (self.window_w, self.window_h),
+            ScalingMode::PixelPerfect | ScalingMode::AspectFit => {
+                let scale_x = self.window_w as f32 / self.canvas_w as f32;
+                let scale_y = self.window_h as f32 / self.canvas_h as f32;
+                let mut scale = scale_x.min(scale_y);
+                if self.mode == ScalingMode::PixelPerfect && scale >= 1.0 {
+                    scale = scale.floor();
+                }
+                (
+                    (self.canvas_w as f32 * scale).round() as u32,
+                    (self.canvas_h as f32 * scale).round() as u32,
+                )
+            }
+        };
+
+        self.viewport = Viewport {
+            x: (self.window_w as i32 - w as i32) / 2,
+            y: (self.window_h as i32 - h as i32) / 2,
+            w,
+            h,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(x: i32, y: i32, w: u32, h: u32) -> Viewport {
+        Viewport { x, y, w, h }
+    }
+
+    fn scaler(mode: ScalingMode, window_w: u32, window_h: u32) -> Scaler {
+        let mut scaler = Scaler::new(mode, 100, 50);
+        scaler.set_window_size(window_w, window_h);
+        scaler
+    }
+
+    fn canvas(scaler: &Scaler, x: f64, y: f64) -> Option<(f32, f32)> {
+        scaler.window_to_canvas(x, y).map(|p| (p.x, p.y))
+    }
+
+    #[test]
+    fn viewport_per_mode() {
+        let pixel_perfect = scaler(ScalingMode::PixelPerfect, 350, 200);
+        assert_eq!(pixel_perfect.viewport(), viewport(25, 25, 300, 150));
+
+        let aspect_fit = scaler(ScalingMode::AspectFit, 350, 200);
+        assert_eq!(aspect_fit.viewport(), viewport(0, 12, 350, 175));
+
+        let stretch = scaler(ScalingMode::Stretch, 350, 200);
+        assert_eq!(stretch.viewport(), viewport(0, 0, 350, 200));
+    }
+
+    #[test]
+    fn set_mode_updates_viewport() {
+        let mut scaler = scaler(ScalingMode::Stretch, 350, 200);
+        scaler.set_mode(ScalingMode::PixelPerfect);
+        assert_eq!(scaler.viewport(), viewport(25, 25, 300, 150));
+    }
+
+    #[test]
+    fn pixel_perfect_below_1x_fits_aspect() {
+        let scaler = scaler(ScalingMode::PixelPerfect, 50, 50);
+        assert_eq!(scaler.viewport(), viewport(0, 12, 50, 25));
+    }
+
+    #[test]
+    fn window_to_canvas_flips_y() {
+        let scaler = scaler(ScalingMode::PixelPerfect, 200, 100);
+        assert_eq!(canvas(&scaler, 0.0, 1.0), Some((0.0, 49.5)));
+        assert_eq!(canvas(&scaler, 100.0, 99.0), Some((50.0, 0.5)));
+    }
+
+    #[test]
+    fn window_to_canvas_ignores_bars() {
+        // Letterboxed with 25 pixel bars on every side
+        let scaler = scaler(ScalingMode::PixelPerfect, 350, 200);
+        assert_eq!(canvas(&scaler, 10.0, 100.0), None);
+        assert_eq!(canvas(&scaler, 340.0, 100.0), None);
+        assert_eq!(canvas(&scaler, 100.0, 10.0), None);
+        assert_eq!(canvas(&scaler, 100.0, 190.0), None);
+        assert_eq!(canvas(&scaler, 25.0, 175.0), Some((0.0, 0.0)));
+    }
+}
@@ -0,0 +1,106 @@
+use math::{Rect, Trans2, Vec2};
+
+use super::atlas::TextureRegion;
+use super::{Color, GlTexture2D};
+
+/// How to place a texture on the canvas.
+///
+/// All positions and sizes are in canvas pixels with the origin at the bottom
+/// left. The sprite is scaled and rotated around its anchor, which is then put
+/// at `pos`.
+///
+///     let sprite = Sprite::for_texture(&bird)
+///         .at(100.0, 200.0)
+///         .with_anchor(0.5, 0.5)
+///         .with_rotation(-0.3);
+#[derive(Clone, Copy, Debug)]
+pub struct Sprite {
+    pub pos: Vec2,
+    /// Size before scaling
+    pub size: Vec2,
+    /// Pivot in sprite space, `(0, 0)` is the bottom left corner and `(1, 1)`
+    /// the top right one
+    pub anchor: Vec2,
+    /// Radians, counter clockwise
+    pub rotation: f32,
+    /// Negative values flip the sprite around its anchor
+    pub scale: Vec2,
+    pub tint: Color,
+}
+
+impl Sprite {
+    pub fn new(w: f32, h: f32) -> Sprite {
+        Sprite {
+            pos: Vec2::zero(),
+            size: Vec2::new(w, h),
+            anchor: Vec2::zero(),
+            rotation: 0.0,
+            scale: Vec2::new(1.0, 1.0),
+            tint: Color::white(),
+        }
+    }
+
+    /// Sprite covering the destination rectangle `rect`
+    pub fn from_rect(rect: &Rect) -> Sprite {
+        Sprite::new(rect.w, rect.h).at(rect.x, rect.y)
+    }
+
+    /// Sprite with the natural size of `texture`
+    pub fn for_texture(texture: &GlTexture2D) -> Sprite {
+        Sprite::new(texture.width() as f32, texture.height() as f32)
+    }
+
+    /// Sprite with the natural size of `region`
+    pub fn for_region(region: &TextureRegion) -> Sprite {
+        Sprite::new(region.w as f32, region.h as f32)
+    }
+
+    pub fn at(mut self, x: f32, y: f32) -> Sprite {
+        self.pos = Vec2::new(x, y);
+        self
+    }
+
+    pub fn with_size(mut self, w: f32, h: f32) -> Sprite {
+        self.size = Vec2::new(w, h);
+        self
+    }
+
+    pub fn with_anchor(mut self, x: f32, y: f32) -> Sprite {
+        self.anchor = Vec2::new(x, y);
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Sprite {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, x: f32, y: f32) -> Sprite {
+        self.scale = Vec2::new(x, y);
+        self
+    }
+
+    pub fn flip_x(mut self) -> Sprite {
+        self.scale.x = -self.scale.x;
+        self
+    }
+
+    pub fn flip_y(mut self) -> Sprite {
+        self.scale.y = -self.scale.y;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Sprite {
+        self.tint = tint;
+        self
+    }
+
+    /// Transform from the unit quad to canvas pixels
+    pub fn trans(&self) -> Trans2 {
+        Trans2::translate(self.pos.x, self.pos.y)
+            * Trans2::rotate(self.rotation)
+            * Trans2::scale(self.scale.x, self.scale.y)
+            * Trans2::translate(-self.anchor.x * self.size.x, -self.anchor.y * self.size.y)
+            * Trans2::scale(self.size.x, self.size.y)
+    }
+}
@@ -0,0 +1,355 @@
+use std;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::rc::Rc;
+
+mod stb_truetype;
+
+use self::stb_truetype::*;
+
+use super::atlas::{ShelfPacker, TextureRegion};
+use super::font::{BitmapFont, FontDescriptor, Glyph};
+use super::{GlContext, GlTexture2D};
+
+/// Size of a new glyph atlas, it doubles whenever it runs out of room
+const INITIAL_ATLAS_SIZE: u32 = 256;
+const MAX_ATLAS_SIZE: u32 = 2048;
+/// Empty pixels between glyphs so they don't bleed into each other
+const GLYPH_PADDING: u32 = 1;
+
+/// TrueType or OpenType font file. Glyphs are rasterized on demand by a
+/// `GlyphCache` for each pixel size.
+pub struct TrueTypeFont {
+    /// `info` points into this
+    _data: Vec<u8>,
+    info: stbtt_fontinfo,
+}
+
+/// Coverage of a single glyph, one byte per pixel
+pub struct RasterizedGlyph {
+    pub w: u32,
+    pub h: u32,
+    /// Offset of the bitmap's top left corner from the pen position on the
+    /// baseline, y pointing down
+    pub offset_x: i32,
+    pub offset_y: i32,
+    /// How far the pen moves after this glyph
+    pub advance: f32,
+    /// Rows top to bottom
+    pub pixels: Vec<u8>,
+}
+
+impl TrueTypeFont {
+    /// # Safety
+    ///
+    /// See `from_bytes`, the file has to be a well-formed font.
+    pub unsafe fn load<P: AsRef<Path>>(path: P) -> io::Result<TrueTypeFont> {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        TrueTypeFont::from_bytes(data)
+    }
+
+    /// Uses the first font of `data`, which may also be a font collection.
+    ///
+    /// # Safety
+    ///
+    /// `data` has to be a well-formed font, e.g. one shipped with the game.
+    /// stb_truetype follows the offsets in the font's tables without bounds
+    /// checks, so a corrupt or malicious file makes it read out of bounds.
+    pub unsafe fn from_bytes(data: Vec<u8>) -> io::Result<TrueTypeFont> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Not a TrueType font");
+        // Too short for the header, `stbtt_GetFontOffsetForIndex` reads that far
+        if data.len() < 12 {
+            return Err(invalid());
+        }
+
+        let mut info = stbtt_fontinfo {
+            userdata: std::ptr::null_mut(),
+            data: std::ptr::null_mut(),
+            fontstart: 0,
+            num_glyphs: 0,
+            loca: 0,
+            head: 0,
+            glyf: 0,
+            hhea: 0,
+            hmtx: 0,
+            kern: 0,
+            index_map: 0,
+            index_to_loc_format: 0,
+        };
+        let offset = stbtt_GetFontOffsetForIndex(data.as_ptr(), 0);
+        if offset < 0 || stbtt_InitFont(&mut info, data.as_ptr(), offset) == 0 {
+            return Err(invalid());
+        }
+
+        Ok(TrueTypeFont { _data: data, info })
+    }
+
+    /// Scale from font units to pixels that makes the font `pixel_height`
+    /// pixels tall from the highest ascender to the lowest descender.
+    pub fn scale_for_pixel_height(&self, pixel_height: f32) -> f32 {
+        unsafe { stbtt_ScaleForPixelHeight(&self.info, pixel_height) }
+    }
+
+    /// Ascent, descent and line gap in font units. Descent is negative.
+    pub fn v_metrics(&self) -> (i32, i32, i32) {
+        let mut ascent = 0;
+        let mut descent = 0;
+        let mut line_gap = 0;
+        unsafe { stbtt_GetFontVMetrics(&self.info, &mut ascent, &mut descent, &mut line_gap) };
+        (ascent, descent, line_gap)
+    }
+
+    /// Index of the glyph for `c`, `None` if the font doesn't have one.
+    pub fn glyph_index(&self, c: char) -> Option<i32> {
+        match unsafe { stbtt_FindGlyphIndex(&self.info, c as i32) } {
+            0 => None,
+            index => Some(index),
+        }
+    }
+
+    /// Extra advance between two glyphs in font units
+    pub fn kerning(&self, first: i32, second: i32) -> i32 {
+        unsafe { stbtt_GetGlyphKernAdvance(&self.info, first, second) }
+    }
+
+    pub fn rasterize(&self, glyph_index: i32, scale: f32) -> RasterizedGlyph {
+        let mut advance = 0;
+        let mut left_side_bearing = 0;
+        let (mut x0, mut y0, mut x1, mut y1) = (0, 0, 0, 0);
+        unsafe {
+            stbtt_GetGlyphHMetrics(
+                &self.info,
+                glyph_index,
+                &mut advance,
+                &mut left_side_bearing,
+            );
+            stbtt_GetGlyphBitmapBox(
+                &self.info,
+                glyph_index,
+                scale,
+                scale,
+                &mut x0,
+                &mut y0,
+                &mut x1,
+                &mut y1,
+            );
+        }
+
+        let w = (x1 - x0).max(0) as u32;
+        let h = (y1 - y0).max(0) as u32;
+        let mut pixels = vec![0; w as usize * h as usize];
+        if !pixels.is_empty() {
+            unsafe {
+                stbtt_MakeGlyphBitmap(
+                    &self.info,
+                    pixels.as_mut_ptr(),
+                    w as i32,
+                    h as i32,
+                    w as i32,
+                    scale,
+                    scale,
+                    glyph_index,
+                );
+            }
+        }
+
+        RasterizedGlyph {
+            w,
+            h,
+            offset_x: x0,
+            offset_y: y0,
+            advance: advance as f32 * scale,
+            pixels,
+        }
+    }
+}
+
+/// A `TrueTypeFont` at one pixel size, rasterized into an `A8` texture as
+/// characters get used.
+///
+/// Call `prepare` with every string before laying it out with `font`, or use
+/// `Graphics::draw_truetype_text` which does both.
+pub struct GlyphCache {
+    font: Rc<TrueTypeFont>,
+    scale: f32,
+    bitmap: BitmapFont,
+    packer: ShelfPacker,
+    /// Copy of the texture, so it can be grown without reading it back
+    pixels: Vec<u8>,
+    /// Glyph index of every character seen so far, `None` if the font has no
+    /// glyph for it
+    indices: HashMap<char, Option<i32>>,
+    kerned: HashSet<(char, char)>,
+}
+
+impl GlyphCache {
+    pub fn new(ctx: &mut GlContext, font: Rc<TrueTypeFont>, pixel_height: f32) -> GlyphCache {
+        let scale = font.scale_for_pixel_height(pixel_height);
+        let (ascent, descent, line_gap) = font.v_metrics();
+
+        let size = INITIAL_ATLAS_SIZE;
+        let pixels = vec![0; size as usize * size as usize];
+        let texture = GlTexture2D::from_a8(ctx, size, size, &pixels);
+        let descriptor = FontDescriptor {
+            line_height: ((ascent - descent + line_gap) as f32 * scale).round() as u32,
+            base: (ascent as f32 * scale).round() as u32,
+            page: String::new(),
+            glyphs: HashMap::new(),
+            kernings: HashMap::new(),
+        };
+
+        GlyphCache {
+            font,
+            scale,
+            bitmap: BitmapFont::new(texture, descriptor).unwrap(),
+            packer: ShelfPacker::new(size, size, GLYPH_PADDING),
+            pixels,
+            indices: HashMap::new(),
+            kerned: HashSet::new(),
+        }
+    }
+
+    /// Glyphs rasterized so far, ready to be drawn like any bitmap font
+    pub fn font(&self) -> &BitmapFont {
+        &self.bitmap
+    }
+
+    pub fn truetype_font(&self) -> &Rc<TrueTypeFont> {
+        &self.font
+    }
+
+    /// Rasterizes the characters of `text` that aren't cached yet.
+    ///
+    /// Once the texture has reached its maximum size and is full, the cache
+    /// starts over with only the characters of `text`. Returns `false` if even
+    /// those don't fit, then some of them are missing from `font`.
+    pub fn prepare(&mut self, ctx: &mut GlContext, text: &str) -> bool {
+        if self.try_prepare(ctx, text) {
+            return true;
+        }
+        self.clear(ctx);
+        self.try_prepare(ctx, text)
+    }
+
+    /// Forgets every glyph, e.g. after drawing lots of text that won't be
+    /// shown again.
+    pub fn clear(&mut self, ctx: &mut GlContext) {
+        let (w, h) = (self.packer.width(), self.packer.height());
+        for p in self.pixels.iter_mut() {
+            *p = 0;
+        }
+        // A new texture, pending draws may still use the glyphs of the old one
+        self.bitmap
+            .replace_texture(GlTexture2D::from_a8(ctx, w, h, &self.pixels));
+        self.bitmap.clear_glyphs();
+        self.packer.clear();
+        self.indices.clear();
+        self.kerned.clear();
+    }
+
+    /// Returns `false` if the texture ran full.
+    fn try_prepare(&mut self, ctx: &mut GlContext, text: &str) -> bool {
+        let mut prev = None;
+        for c in text.chars() {
+            if c == '\n' {
+                prev = None;
+                continue;
+            }
+
+            let index = match self.indices.get(&c) {
+                Some(&index) => index,
+                None => {
+                    let index = self.font.glyph_index(c);
+                    if let Some(index) = index {
+                        if !self.add_glyph(ctx, c, index) {
+                            return false;
+                        }
+                    }
+                    self.indices.insert(c, index);
+                    index
+                }
+            };
+            // Like `BitmapFont::layout`, missing characters don't break kerning pairs
+            let index = match index {
+                Some(index) => index,
+                None => continue,
+            };
+
+            if let Some((prev_c, prev_index)) = prev {
+                if self.kerned.insert((prev_c, c)) {
+                    let amount = self.font.kerning(prev_index, index) as f32 * self.scale;
+                    let amount = amount.round() as i32;
+                    if amount != 0 {
+                        self.bitmap.insert_kerning(prev_c, c, amount);
+                    }
+                }
+            }
+            prev = Some((c, index));
+        }
+        true
+    }
+
+    fn add_glyph(&mut self, ctx: &mut GlContext, c: char, index: i32) -> bool {
+        let raster = self.font.rasterize(index, self.scale);
+        let region = if raster.w == 0 || raster.h == 0 {
+            TextureRegion::new(0, 0, 0, 0)
+        } else {
+            let region = match self.allocate(ctx, raster.w, raster.h) {
+                Some(region) => region,
+                None => return false,
+            };
+
+            let atlas_w = self.packer.width() as usize;
+            for (y, row) in raster.pixels.chunks(raster.w as usize).enumerate() {
+                let start = (region.y as usize + y) * atlas_w + region.x as usize;
+                self.pixels[start..start + row.len()].copy_from_slice(row);
+            }
+            self.bitmap
+                .texture_mut()
+                .update_a8(ctx, &region, &raster.pixels);
+            region
+        };
+
+        let glyph = Glyph {
+            region,
+            offset_x: raster.offset_x,
+            offset_y: self.bitmap.base() as i32 + raster.offset_y,
+            advance: raster.advance.round() as i32,
+        };
+        self.bitmap.insert_glyph(c, glyph);
+        true
+    }
+
+    /// Packs a `w` by `h` region, growing the texture if needed.
+    fn allocate(&mut self, ctx: &mut GlContext, w: u32, h: u32) -> Option<TextureRegion> {
+        loop {
+            if let Some(region) = self.packer.pack(w, h) {
+                return Some(region);
+            }
+
+            let (old_w, old_h) = (self.packer.width(), self.packer.height());
+            if old_w >= MAX_ATLAS_SIZE && old_h >= MAX_ATLAS_SIZE {
+                return None;
+            }
+            let (new_w, new_h) = (
+                (old_w * 2).min(MAX_ATLAS_SIZE),
+                (old_h * 2).min(MAX_ATLAS_SIZE),
+            );
+
+            // Keep the glyphs at the same pixels, texcoords follow the new size
+            let mut pixels = vec![0; new_w as usize * new_h as usize];
+            for (y, row) in self.pixels.chunks(old_w as usize).enumerate() {
+                let start = y * new_w as usize;
+                pixels[start..start + row.len()].copy_from_slice(row);
+            }
+            self.bitmap
+                .replace_texture(GlTexture2D::from_a8(ctx, new_w, new_h, &pixels));
+            self.pixels = pixels;
+            self.packer.grow(new_w, new_h);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::os::raw::{c_float, c_int, c_uchar, c_void};
+
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct stbtt_fontinfo {
+    pub userdata: *mut c_void,
+    pub data: *mut c_uchar,
+    pub fontstart: c_int,
+
+    pub num_glyphs: c_int,
+
+    pub loca: c_int,
+    pub head: c_int,
+    pub glyf: c_int,
+    pub hhea: c_int,
+    pub hmtx: c_int,
+    pub kern: c_int,
+    pub index_map: c_int,
+    pub index_to_loc_format: c_int,
+}
+
+extern "C" {
+    pub fn stbtt_GetFontOffsetForIndex(data: *const c_uchar, index: c_int) -> c_int;
+
+    pub fn stbtt_InitFont(info: *mut stbtt_fontinfo, data: *const c_uchar, offset: c_int) -> c_int;
+
+    pub fn stbtt_FindGlyphIndex(info: *const stbtt_fontinfo, unicode_codepoint: c_int) -> c_int;
+
+    pub fn stbtt_ScaleForPixelHeight(info: *const stbtt_fontinfo, pixels: c_float) -> c_float;
+
+    pub fn stbtt_GetFontVMetrics(
+        info: *const stbtt_fontinfo,
+        ascent: *mut c_int,
+        descent: *mut c_int,
+        line_gap: *mut c_int,
+    );
+
+    pub fn stbtt_GetGlyphHMetrics(
+        info: *const stbtt_fontinfo,
+        glyph_index: c_int,
+        advance_width: *mut c_int,
+        left_side_bearing: *mut c_int,
+    );
+
+    pub fn stbtt_GetGlyphKernAdvance(
+        info: *const stbtt_fontinfo,
+        glyph1: c_int,
+        glyph2: c_int,
+    ) -> c_int;
+
+    pub fn stbtt_GetGlyphBitmapBox(
+        font: *const stbtt_fontinfo,
+        glyph: c_int,
+        scale_x: c_float,
+        scale_y: c_float,
+        ix0: *mut c_int,
+        iy0: *mut c_int,
+        ix1: *mut c_int,
+        iy1: *mut c_int,
+    );
+
+    pub fn stbtt_MakeGlyphBitmap(
+        info: *const stbtt_fontinfo,
+        output: *mut c_uchar,
+        out_w: c_int,
+        out_h: c_int,
+        out_stride: c_int,
+        scale_x: c_float,
+        scale_y: c_float,
+        glyph: c_int,
+    );
+}
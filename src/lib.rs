@@ -0,0 +1,10 @@
+extern crate glutin;
+extern crate libc;
+#[cfg(feature = "png")]
+extern crate png;
+
+pub mod game;
+pub mod gfx;
+pub mod math;
+pub mod sim;
+pub mod timestep;
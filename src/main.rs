@@ -1,12 +1,17 @@
+extern crate flapply_bird;
 extern crate glutin;
-extern crate libc;
+
+use std::process;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use glutin::GlContext;
 
-pub mod gfx;
-pub mod math;
+use flapply_bird::game::Game;
+use flapply_bird::gfx::{Camera, Graphics, CANVAS_HEIGHT, CANVAS_WIDTH};
+use flapply_bird::timestep::FixedTimestep;
 
-use gfx::Graphics;
+/// Simulation ticks per second, independent of the display refresh rate
+const TICK_RATE: u32 = 60;
 
 fn main() {
     let mut events_loop = glutin::EventsLoop::new();
@@ -23,30 +28,113 @@ fn main() {
         // gl::ClearColor(0.0, 1.0, 0.0, 1.0);
     }
 
-    let mut gfx = Graphics::new(|symbol| gl_window.get_proc_address(symbol) as *const _);
-    let background_texture = gfx.load_texture("assets/sprites/background_day.png")
-        .unwrap();
+    let mut gfx = match Graphics::new(|symbol| gl_window.get_proc_address(symbol) as *const _) {
+        Ok(gfx) => gfx,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(1);
+        }
+    };
+    // Pick up edited sprites and shaders without restarting while developing
+    gfx.set_hot_reload(cfg!(debug_assertions));
+    // The game is rendered at its native resolution and then scaled up as a
+    // whole, so rotated sprites stay on the pixel grid.
+    let canvas = gfx.create_render_target(CANVAS_WIDTH, CANVAS_HEIGHT);
+
+    let mut timestep = FixedTimestep::new(TICK_RATE);
+    let mut game = match Game::new(&mut gfx, timestep.dt()) {
+        Ok(game) => game,
+        Err(e) => {
+            println!("Failed to load sprites: {}", e);
+            println!("See assets/sprites/README.md for the files the game needs");
+            process::exit(1);
+        }
+    };
 
+    let mut cursor = (0.0, 0.0);
+    let mut take_screenshot = false;
+    let mut last_frame = Instant::now();
     let mut running = true;
     while running {
-        events_loop.poll_events(|event| match event {
-            glutin::Event::WindowEvent { event, .. } => match event {
-                glutin::WindowEvent::Closed => running = false,
-                glutin::WindowEvent::Resized(w, h) => gl_window.resize(w, h),
-                glutin::WindowEvent::KeyboardInput { input, .. } => {
-                    if input.virtual_keycode == Some(glutin::VirtualKeyCode::Escape) {
-                        running = false;
+        events_loop.poll_events(|event| {
+            if let glutin::Event::WindowEvent { event, .. } = event {
+                match event {
+                    glutin::WindowEvent::Closed => running = false,
+                    glutin::WindowEvent::Resized(w, h) => {
+                        gl_window.resize(w, h);
+                        gfx.resize(w, h);
                     }
+                    glutin::WindowEvent::KeyboardInput {
+                        input:
+                            glutin::KeyboardInput {
+                                state: glutin::ElementState::Pressed,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    } => match key {
+                        glutin::VirtualKeyCode::Escape => game.back(),
+                        glutin::VirtualKeyCode::Space => game.flap(),
+                        glutin::VirtualKeyCode::F12 => take_screenshot = true,
+                        glutin::VirtualKeyCode::F2 => {
+                            let mode = gfx.scaling_mode().next();
+                            println!("Scaling mode: {:?}", mode);
+                            gfx.set_scaling_mode(mode);
+                        }
+                        _ => (),
+                    },
+                    glutin::WindowEvent::MouseMoved { position, .. } => cursor = position,
+                    // Clicks on the letterbox bars don't count
+                    glutin::WindowEvent::MouseInput {
+                        state: glutin::ElementState::Pressed,
+                        button: glutin::MouseButton::Left,
+                        ..
+                    } if gfx.window_to_canvas(cursor.0, cursor.1).is_some() => game.flap(),
+                    _ => (),
                 }
-                _ => (),
-            },
-            _ => (),
+            }
         });
 
+        let now = Instant::now();
+        let ticks = timestep.advance(now - last_frame);
+        last_frame = now;
+        for _ in 0..ticks {
+            game.update();
+        }
+
+        if game.is_quit() {
+            running = false;
+        }
+
+        gfx.set_render_target(Some(&canvas));
         gfx.clear();
+        game.draw(&mut gfx, timestep.alpha());
+
+        if take_screenshot {
+            take_screenshot = false;
+            save_screenshot(&mut gfx);
+        }
 
-        gfx.draw_texture(&background_texture);
+        gfx.set_render_target(None);
+        gfx.clear();
+        gfx.set_camera(&Camera::new(CANVAS_WIDTH as f32, CANVAS_HEIGHT as f32));
+        gfx.draw_texture(canvas.texture());
+        gfx.end_frame();
 
         gl_window.swap_buffers().unwrap();
     }
 }
+
+/// Saves what is currently rendered to a PNG in the working directory.
+fn save_screenshot(gfx: &mut Graphics) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = format!("screenshot_{}.png", timestamp);
+
+    match gfx.read_pixels(true).save_png(&path) {
+        Ok(()) => println!("Saved screenshot to {}", path),
+        Err(e) => println!("Failed to save screenshot to {}: {}", path, e),
+    }
+}
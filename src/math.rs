@@ -1,9 +1,15 @@
+use std::ops::Mul;
+
 pub fn next_pow2_u32(value: u32) -> u32 {
     2u32.pow(((value as f32).log2().ceil()) as u32)
 }
 
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
 #[repr(C)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
@@ -19,6 +25,28 @@ impl Vec2 {
     }
 }
 
+/// Axis aligned rectangle, `(x, y)` is the bottom left corner
+#[derive(Clone, Copy, Debug)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect { x, y, w, h }
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+}
+
 /// 2D Affine Transform Matrix
 ///
 ///     | a c x |    | x y o |
@@ -33,7 +61,7 @@ impl Vec2 {
 ///
 /// This matrix use column-major order to store elements
 #[repr(C)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct Trans2 {
     pub a: f32,
     pub b: f32,
@@ -54,6 +82,57 @@ impl Trans2 {
             y: 0.0,
         }
     }
+
+    pub fn translate(x: f32, y: f32) -> Trans2 {
+        Trans2 {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 1.0,
+            x,
+            y,
+        }
+    }
+
+    /// Counter clockwise rotation by `angle` radians
+    pub fn rotate(angle: f32) -> Trans2 {
+        let (sin, cos) = angle.sin_cos();
+        Trans2 {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+
+    pub fn scale(sx: f32, sy: f32) -> Trans2 {
+        Trans2 {
+            a: sx,
+            b: 0.0,
+            c: 0.0,
+            d: sy,
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+}
+
+impl Mul for Trans2 {
+    type Output = Trans2;
+
+    /// `rhs` is applied first, i.e. `(self * rhs) * v == self * (rhs * v)`
+    fn mul(self, rhs: Trans2) -> Trans2 {
+        Trans2 {
+            a: self.a * rhs.a + self.c * rhs.b,
+            b: self.b * rhs.a + self.d * rhs.b,
+            c: self.a * rhs.c + self.c * rhs.d,
+            d: self.b * rhs.c + self.d * rhs.d,
+            x: self.a * rhs.x + self.c * rhs.y + self.x,
+            y: self.b * rhs.x + self.d * rhs.y + self.y,
+        }
+    }
 }
 
 #[repr(C)]
@@ -0,0 +1,89 @@
+use math::Rect;
+
+use super::{FLAP_VELOCITY, GRAVITY, MAX_FALL_SPEED};
+
+pub const BIRD_WIDTH: f32 = 34.0;
+pub const BIRD_HEIGHT: f32 = 24.0;
+
+/// Seconds each wing frame is shown
+const FLAP_FRAME_TIME: f32 = 0.1;
+const NUM_FLAP_FRAMES: usize = 3;
+
+/// Bobbing while waiting for the first flap
+const HOVER_AMPLITUDE: f32 = 4.0;
+const HOVER_PERIOD: f32 = 0.8;
+
+pub struct Bird {
+    /// Center of the bird
+    pub x: f32,
+    pub y: f32,
+    /// `y` before the last update, used to interpolate rendering between ticks
+    pub prev_y: f32,
+    /// Vertical velocity, positive is up
+    pub vy: f32,
+    hover_y: f32,
+    hover_time: f32,
+    frame_time: f32,
+    frame: usize,
+}
+
+impl Bird {
+    pub fn new(x: f32, y: f32) -> Bird {
+        Bird {
+            x,
+            y,
+            prev_y: y,
+            vy: 0.0,
+            hover_y: y,
+            hover_time: 0.0,
+            frame_time: 0.0,
+            frame: 0,
+        }
+    }
+
+    pub fn flap(&mut self) {
+        self.vy = FLAP_VELOCITY;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.prev_y = self.y;
+        self.vy = (self.vy - GRAVITY * dt).max(-MAX_FALL_SPEED);
+        self.y += self.vy * dt;
+    }
+
+    /// Bobs up and down around the starting height instead of falling.
+    pub fn hover(&mut self, dt: f32) {
+        self.prev_y = self.y;
+        self.hover_time = (self.hover_time + dt) % HOVER_PERIOD;
+        let angle = self.hover_time / HOVER_PERIOD * 2.0 * ::std::f32::consts::PI;
+        self.y = self.hover_y + angle.sin() * HOVER_AMPLITUDE;
+    }
+
+    pub fn animate(&mut self, dt: f32) {
+        self.frame_time += dt;
+        while self.frame_time >= FLAP_FRAME_TIME {
+            self.frame_time -= FLAP_FRAME_TIME;
+            self.frame = (self.frame + 1) % NUM_FLAP_FRAMES;
+        }
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y - BIRD_HEIGHT / 2.0
+    }
+
+    /// Hit box is slightly smaller than the sprite so grazing a pipe with a
+    /// transparent pixel doesn't count.
+    pub fn hit_box(&self) -> Rect {
+        const INSET: f32 = 3.0;
+        Rect {
+            x: self.x - BIRD_WIDTH / 2.0 + INSET,
+            y: self.y - BIRD_HEIGHT / 2.0 + INSET,
+            w: BIRD_WIDTH - INSET * 2.0,
+            h: BIRD_HEIGHT - INSET * 2.0,
+        }
+    }
+}
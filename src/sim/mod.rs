@@ -0,0 +1,377 @@
+//! Window and renderer independent game simulation.
+//!
+//! Everything here is deterministic for a given seed and sequence of inputs, so
+//! it can be stepped in tests without a display.
+
+mod bird;
+mod pipe;
+mod rng;
+
+pub use self::bird::{Bird, BIRD_HEIGHT, BIRD_WIDTH};
+pub use self::pipe::{Pipe, PIPE_GAP, PIPE_WIDTH};
+pub use self::rng::Rng;
+
+pub const SCREEN_WIDTH: f32 = 288.0;
+pub const SCREEN_HEIGHT: f32 = 512.0;
+pub const GROUND_HEIGHT: f32 = 112.0;
+
+/// All speeds are in pixels per second, y axis points up
+pub const GRAVITY: f32 = 1200.0;
+pub const FLAP_VELOCITY: f32 = 380.0;
+pub const MAX_FALL_SPEED: f32 = 500.0;
+pub const SCROLL_SPEED: f32 = 120.0;
+
+pub const BIRD_X: f32 = SCREEN_WIDTH / 3.0;
+pub const PIPE_SPACING: f32 = 160.0;
+/// Minimum distance between the gap and the ground or the top of the screen
+pub const PIPE_GAP_MARGIN: f32 = 60.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Bird hovers in place until the first flap
+    Ready,
+    /// Bird is flying and pipes are scrolling
+    Playing,
+    /// Bird hit a pipe and is falling to the ground
+    Falling,
+    /// Bird is lying on the ground
+    Dead,
+}
+
+/// Player input sampled for one tick
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Input {
+    pub flap: bool,
+}
+
+/// Things that happened during one tick
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Flapped,
+    Scored(u32),
+    HitPipe,
+    HitGround,
+}
+
+pub struct World {
+    dt: f32,
+    rng: Rng,
+    phase: Phase,
+    bird: Bird,
+    pipes: Vec<Pipe>,
+    ground_offset: f32,
+    prev_ground_offset: f32,
+    score: u32,
+    ticks: u64,
+}
+
+impl World {
+    /// Creates a world which advances `dt` seconds per `step`.
+    pub fn new(seed: u32, dt: f32) -> World {
+        let mut world = World {
+            dt,
+            rng: Rng::new(seed),
+            phase: Phase::Ready,
+            bird: Bird::new(BIRD_X, 0.0),
+            pipes: Vec::new(),
+            ground_offset: 0.0,
+            prev_ground_offset: 0.0,
+            score: 0,
+            ticks: 0,
+        };
+        world.reset();
+        world
+    }
+
+    /// Starts a new round. The RNG is not reseeded so every round is different.
+    pub fn reset(&mut self) {
+        self.phase = Phase::Ready;
+        self.bird = Bird::new(BIRD_X, (SCREEN_HEIGHT + GROUND_HEIGHT) / 2.0);
+        self.pipes.clear();
+        self.score = 0;
+
+        // Leave the player some room before the first pipe
+        let mut x = SCREEN_WIDTH * 1.5;
+        while x < SCREEN_WIDTH * 2.0 + PIPE_SPACING {
+            self.spawn_pipe(x);
+            x += PIPE_SPACING;
+        }
+    }
+
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn bird(&self) -> &Bird {
+        &self.bird
+    }
+
+    pub fn pipes(&self) -> &[Pipe] {
+        &self.pipes
+    }
+
+    pub fn ground_offset(&self) -> f32 {
+        self.ground_offset
+    }
+
+    pub fn prev_ground_offset(&self) -> f32 {
+        self.prev_ground_offset
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// Number of steps since the world was created
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Advances the simulation by one tick.
+    pub fn step(&mut self, input: Input) -> Vec<Event> {
+        let mut events = Vec::new();
+        let dt = self.dt;
+
+        self.ticks += 1;
+        self.prev_ground_offset = self.ground_offset;
+        for pipe in self.pipes.iter_mut() {
+            pipe.prev_x = pipe.x;
+        }
+
+        if self.phase == Phase::Ready && input.flap {
+            self.phase = Phase::Playing;
+        }
+
+        if self.phase == Phase::Playing && input.flap {
+            self.bird.flap();
+            events.push(Event::Flapped);
+        }
+
+        match self.phase {
+            Phase::Ready => self.bird.hover(dt),
+            Phase::Playing | Phase::Falling => self.bird.update(dt),
+            Phase::Dead => self.bird.prev_y = self.bird.y,
+        }
+
+        let scroll = SCROLL_SPEED * dt;
+        if self.phase == Phase::Ready || self.phase == Phase::Playing {
+            self.bird.animate(dt);
+            self.ground_offset += scroll;
+        }
+
+        if self.phase == Phase::Playing {
+            for pipe in self.pipes.iter_mut() {
+                pipe.x -= scroll;
+                if !pipe.passed && pipe.right() < self.bird.x {
+                    pipe.passed = true;
+                    self.score += 1;
+                    events.push(Event::Scored(self.score));
+                }
+            }
+
+            self.pipes.retain(|pipe| pipe.right() > 0.0);
+            let last_x = self.pipes.last().map(|pipe| pipe.x).unwrap_or(SCREEN_WIDTH);
+            if last_x + PIPE_SPACING < SCREEN_WIDTH + PIPE_WIDTH {
+                self.spawn_pipe(last_x + PIPE_SPACING);
+            }
+
+            let hit_box = self.bird.hit_box();
+            let hit_pipe = self.pipes.iter().any(|pipe| {
+                hit_box.intersects(&pipe.lower_rect()) || hit_box.intersects(&pipe.upper_rect())
+            });
+            if hit_pipe {
+                self.phase = Phase::Falling;
+                events.push(Event::HitPipe);
+            }
+        }
+
+        if self.phase != Phase::Dead && self.bird.bottom() <= GROUND_HEIGHT {
+            self.bird.y = GROUND_HEIGHT + BIRD_HEIGHT / 2.0;
+            self.bird.vy = 0.0;
+            self.phase = Phase::Dead;
+            events.push(Event::HitGround);
+        }
+
+        events
+    }
+
+    fn spawn_pipe(&mut self, x: f32) {
+        let min = GROUND_HEIGHT + PIPE_GAP_MARGIN;
+        let max = SCREEN_HEIGHT - PIPE_GAP_MARGIN - PIPE_GAP;
+        let gap_y = self.rng.range_f32(min, max).round();
+        self.pipes.push(Pipe::new(x, gap_y));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    /// Flaps whenever the bird sinks close to the bottom of the next gap.
+    fn autopilot(world: &World) -> Input {
+        let bird = world.bird();
+        let hit_box = bird.hit_box();
+        let next = world
+            .pipes()
+            .iter()
+            .find(|pipe| pipe.right() > hit_box.x)
+            .unwrap();
+        Input {
+            flap: bird.vy <= 0.0 && hit_box.y < next.gap_y + 10.0,
+        }
+    }
+
+    /// Autopilot that stops flapping after reaching `score`
+    fn autopilot_until_score(score: u32) -> impl FnMut(&World) -> Input {
+        move |world| {
+            if world.score() < score {
+                autopilot(world)
+            } else {
+                Input::default()
+            }
+        }
+    }
+
+    fn flap() -> Input {
+        Input { flap: true }
+    }
+
+    /// Steps until the world is in `phase`, returning every event on the way.
+    fn step_until<F: FnMut(&World) -> Input>(
+        world: &mut World,
+        phase: Phase,
+        mut input: F,
+    ) -> Vec<Event> {
+        let mut events = Vec::new();
+        for _ in 0..10_000 {
+            if world.phase() == phase {
+                return events;
+            }
+            let input = input(world);
+            events.extend(world.step(input));
+        }
+        panic!("World never reached {:?}", phase);
+    }
+
+    #[test]
+    fn waits_for_first_flap() {
+        let mut world = World::new(1, DT);
+        let gap_x = world.pipes()[0].x;
+        for _ in 0..600 {
+            assert!(world.step(Input::default()).is_empty());
+        }
+        assert_eq!(world.phase(), Phase::Ready);
+        assert_eq!(world.pipes()[0].x, gap_x);
+
+        assert_eq!(world.step(flap()), vec![Event::Flapped]);
+        assert_eq!(world.phase(), Phase::Playing);
+    }
+
+    #[test]
+    fn scores_once_per_pipe() {
+        let mut world = World::new(1, DT);
+        world.step(flap());
+        let mut events = Vec::new();
+        for _ in 0..60 * 60 {
+            let input = autopilot(&world);
+            events.extend(world.step(input));
+        }
+
+        assert_eq!(world.phase(), Phase::Playing);
+        let scores: Vec<_> = events
+            .iter()
+            .filter_map(|event| match *event {
+                Event::Scored(score) => Some(score),
+                _ => None,
+            })
+            .collect();
+        // A minute at 120 pixels per second passes a pipe every 160 pixels
+        assert!(scores.len() > 40, "Only scored {}", scores.len());
+        assert_eq!(scores, (1..scores.len() as u32 + 1).collect::<Vec<_>>());
+        assert_eq!(world.score(), scores.len() as u32);
+    }
+
+    #[test]
+    fn hits_pipe_then_falls_to_the_ground() {
+        let mut world = World::new(1, DT);
+        // Stay near the top of the screen, above any gap
+        let events = step_until(&mut world, Phase::Falling, |world| Input {
+            flap: world.bird().vy <= 0.0 && world.bird().y < SCREEN_HEIGHT - 30.0,
+        });
+        assert_eq!(events.last(), Some(&Event::HitPipe));
+        assert!(!events.contains(&Event::HitGround));
+
+        // Flaps don't work while falling
+        let events = step_until(&mut world, Phase::Dead, |_| flap());
+        assert_eq!(events, vec![Event::HitGround]);
+        assert_eq!(world.bird().bottom(), GROUND_HEIGHT);
+    }
+
+    #[test]
+    fn falling_without_flaps_hits_the_ground() {
+        let mut world = World::new(1, DT);
+        world.step(flap());
+        let events = step_until(&mut world, Phase::Dead, |_| Input::default());
+        assert_eq!(events, vec![Event::HitGround]);
+        assert_eq!(world.score(), 0);
+    }
+
+    #[test]
+    fn dead_bird_stays_on_the_ground() {
+        let mut world = World::new(1, DT);
+        world.step(flap());
+        step_until(&mut world, Phase::Dead, |_| Input::default());
+
+        let y = world.bird().y;
+        let ground_offset = world.ground_offset();
+        for _ in 0..600 {
+            assert!(world.step(flap()).is_empty());
+        }
+        assert_eq!(world.phase(), Phase::Dead);
+        assert_eq!(world.bird().y, y);
+        assert_eq!(world.bird().prev_y, y);
+        assert_eq!(world.ground_offset(), ground_offset);
+    }
+
+    #[test]
+    fn reset_starts_a_new_round() {
+        let mut world = World::new(1, DT);
+        world.step(flap());
+        step_until(&mut world, Phase::Dead, autopilot_until_score(3));
+        assert!(world.score() >= 3);
+
+        world.reset();
+        assert_eq!(world.phase(), Phase::Ready);
+        assert_eq!(world.score(), 0);
+        assert!(world.pipes().iter().all(|pipe| !pipe.passed));
+    }
+
+    /// Events and final state of a run with the autopilot, crashing after
+    /// `score` points
+    fn run(seed: u32, score: u32) -> (Vec<(u64, Event)>, Vec<f32>, u64) {
+        let mut world = World::new(seed, DT);
+        let mut events = Vec::new();
+        let mut input = autopilot_until_score(score);
+        world.step(flap());
+        while world.phase() != Phase::Dead {
+            let tick_input = input(&world);
+            let ticks = world.ticks();
+            events.extend(world.step(tick_input).into_iter().map(|e| (ticks, e)));
+        }
+        let gaps = world.pipes().iter().map(|pipe| pipe.gap_y).collect();
+        (events, gaps, world.ticks())
+    }
+
+    #[test]
+    fn same_seed_same_run() {
+        let first = run(42, 20);
+        assert_eq!(first, run(42, 20));
+        assert_ne!(first.1, run(43, 20).1);
+    }
+}
@@ -0,0 +1,55 @@
+use math::Rect;
+
+use super::SCREEN_HEIGHT;
+
+pub const PIPE_WIDTH: f32 = 52.0;
+pub const PIPE_GAP: f32 = 100.0;
+
+pub struct Pipe {
+    /// Left edge of the pipe pair
+    pub x: f32,
+    /// `x` before the last update, used to interpolate rendering between ticks
+    pub prev_x: f32,
+    /// Bottom edge of the gap
+    pub gap_y: f32,
+    /// Whether the bird has already been scored for this pipe
+    pub passed: bool,
+}
+
+impl Pipe {
+    pub fn new(x: f32, gap_y: f32) -> Pipe {
+        Pipe {
+            x,
+            prev_x: x,
+            gap_y,
+            passed: false,
+        }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + PIPE_WIDTH
+    }
+
+    pub fn gap_top(&self) -> f32 {
+        self.gap_y + PIPE_GAP
+    }
+
+    pub fn lower_rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: 0.0,
+            w: PIPE_WIDTH,
+            h: self.gap_y,
+        }
+    }
+
+    /// Extends past the top of the screen so flying over the pipe still hits
+    pub fn upper_rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: self.gap_top(),
+            w: PIPE_WIDTH,
+            h: SCREEN_HEIGHT,
+        }
+    }
+}
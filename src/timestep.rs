@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+/// Fixed timestep accumulator.
+///
+/// Frame time reported by the window is fed into `advance`, which returns how
+/// many simulation ticks of `dt()` seconds should run this frame. What's left in
+/// the accumulator is exposed as `alpha()` so rendering can interpolate between
+/// the previous and the current simulation state.
+pub struct FixedTimestep {
+    tick_rate: u32,
+    dt: f32,
+    accumulator: f32,
+    max_ticks_per_frame: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: u32) -> FixedTimestep {
+        assert!(tick_rate > 0);
+
+        FixedTimestep {
+            tick_rate,
+            dt: 1.0 / tick_rate as f32,
+            accumulator: 0.0,
+            // Don't try to catch up more than a quarter second, e.g. after the
+            // window was dragged, otherwise we would never catch up again.
+            max_ticks_per_frame: (tick_rate / 4).max(1),
+        }
+    }
+
+    /// Number of simulation ticks per second
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    /// Seconds per tick
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    /// Adds `elapsed` to the accumulator and returns how many ticks to simulate.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9;
+
+        let mut ticks = 0;
+        while self.accumulator >= self.dt {
+            self.accumulator -= self.dt;
+            ticks += 1;
+        }
+
+        if ticks > self.max_ticks_per_frame {
+            ticks = self.max_ticks_per_frame;
+            self.accumulator = 0.0;
+        }
+
+        ticks
+    }
+
+    /// How far we are between the last tick and the next one, in `[0, 1]`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.dt).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn ticks_per_advance() {
+        let mut timestep = FixedTimestep::new(100);
+        assert_eq!(timestep.advance(ms(5)), 0);
+        assert_eq!(timestep.advance(ms(5)), 1);
+        assert_eq!(timestep.advance(ms(35)), 3);
+        assert!((timestep.alpha() - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn catch_up_is_capped() {
+        let mut timestep = FixedTimestep::new(100);
+        assert_eq!(timestep.advance(ms(1000)), 25);
+
+        // The rest of the second is dropped instead of being caught up later
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(ms(5)), 0);
+        assert_eq!(timestep.advance(ms(10)), 1);
+    }
+
+    #[test]
+    fn alpha_stays_in_range() {
+        let mut timestep = FixedTimestep::new(60);
+        for i in 0..1000 {
+            timestep.advance(Duration::from_micros(i * 37 % 50_000));
+            let alpha = timestep.alpha();
+            assert!((0.0..=1.0).contains(&alpha), "alpha {}", alpha);
+        }
+    }
+}
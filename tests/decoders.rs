@@ -0,0 +1,99 @@
+//! Decodes the PNG fixtures in `tests/images`, which cover every color type,
+//! bit depth and kind of transparency, with stb_image and with the Rust PNG
+//! decoder and checks both produce the same pixels.
+
+#![cfg(feature = "png")]
+
+extern crate flapply_bird;
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use flapply_bird::gfx::image::{Decoder, GeneralImage, ImageError};
+
+const FIXTURE_DIR: &str = "tests/images";
+
+/// Channel counts every fixture is decoded with, `None` keeps the file's
+const CHANNELS: [Option<u32>; 5] = [None, Some(1), Some(2), Some(3), Some(4)];
+
+#[test]
+fn decoders_agree() {
+    let paths = fixtures().unwrap();
+    assert!(!paths.is_empty(), "No fixtures in {}", FIXTURE_DIR);
+
+    let mut failures = Vec::new();
+    for path in &paths {
+        let bytes = fs::read(path).unwrap();
+        for &channels in CHANNELS.iter() {
+            let stb = GeneralImage::from_bytes_with_decoder(&bytes, channels, Decoder::Stb);
+            let png = GeneralImage::from_bytes_with_decoder(&bytes, channels, Decoder::Png);
+            if let Err(reason) = compare(stb, png) {
+                failures.push(match channels {
+                    Some(channels) => {
+                        format!("{} as {} channels: {}", path.display(), channels, reason)
+                    }
+                    None => format!("{}: {}", path.display(), reason),
+                });
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} decoder checks failed:\n{}",
+        failures.len(),
+        paths.len() * CHANNELS.len(),
+        failures.join("\n")
+    );
+}
+
+fn fixtures() -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(FIXTURE_DIR)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) == Some("png") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Why the images decoded by stb_image and the Rust decoder differ
+fn compare(
+    stb: Result<GeneralImage, ImageError>,
+    png: Result<GeneralImage, ImageError>,
+) -> Result<(), String> {
+    let stb = stb.map_err(|e| format!("stb_image failed: {}", e))?;
+    let png = png.map_err(|e| format!("png failed: {}", e))?;
+
+    let size = |image: &GeneralImage| (image.width(), image.height(), image.num_component());
+    if size(&stb) != size(&png) {
+        return Err(format!(
+            "stb_image decoded {:?} but png {:?} (width, height, channels)",
+            size(&stb),
+            size(&png)
+        ));
+    }
+
+    let n = stb.num_bytes_per_pixel();
+    let differing = stb
+        .bytes()
+        .chunks(n)
+        .zip(png.bytes().chunks(n))
+        .position(|(a, b)| a != b);
+    match differing {
+        Some(i) => {
+            let (x, y) = (i as u32 % stb.width(), i as u32 / stb.width());
+            Err(format!(
+                "pixel ({}, {}) is {:?} from stb_image but {:?} from png",
+                x,
+                y,
+                &stb.bytes()[i * n..(i + 1) * n],
+                &png.bytes()[i * n..(i + 1) * n]
+            ))
+        }
+        None => Ok(()),
+    }
+}
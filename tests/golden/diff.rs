@@ -0,0 +1,83 @@
+use flapply_bird::gfx::image::RgbaImage;
+
+/// Result of comparing a rendered image against its reference
+pub enum Comparison {
+    Match,
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    Mismatch(Mismatch),
+}
+
+/// Pixels that differ by more than the tolerance in at least one channel
+pub struct Mismatch {
+    pub count: usize,
+    /// Largest difference of any channel
+    pub max_difference: u8,
+    /// Smallest rectangle containing every mismatched pixel, `(x, y)` is the
+    /// top left corner like in image editors
+    pub bounds: (u32, u32, u32, u32),
+    /// Mismatched pixels in red over a faded copy of the rendered image
+    pub diff: RgbaImage,
+}
+
+/// Compares `actual` against `expected` channel by channel. Differences of up
+/// to `tolerance` are accepted since rasterizers round slightly differently.
+pub fn compare(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> Comparison {
+    let (w, h) = (actual.width(), actual.height());
+    if (expected.width(), expected.height()) != (w, h) {
+        return Comparison::SizeMismatch {
+            expected: (expected.width(), expected.height()),
+            actual: (w, h),
+        };
+    }
+
+    let mut diff = RgbaImage::new(w, h);
+    let mut count = 0;
+    let mut max_difference = 0;
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (w, h, 0, 0);
+
+    let pixels = expected
+        .bytes()
+        .chunks(4)
+        .zip(actual.bytes().chunks(4))
+        .zip(diff.bytes_mut().chunks_mut(4));
+    for (i, ((e, a), d)) in pixels.enumerate() {
+        let difference = e
+            .iter()
+            .zip(a)
+            .map(|(&e, &a)| e.max(a) - e.min(a))
+            .max()
+            .unwrap_or(0);
+
+        if difference > tolerance {
+            let x = i as u32 % w;
+            let y = i as u32 / w;
+            count += 1;
+            max_difference = max_difference.max(difference);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+
+            d.copy_from_slice(&[255, 0, 0, 255]);
+        } else {
+            // Dimmed grey so the mismatches stand out but remain locatable
+            let luma = (a[0] as u32 * 3 + a[1] as u32 * 6 + a[2] as u32) / 10;
+            let grey = (luma / 4 + 32) as u8;
+            d.copy_from_slice(&[grey, grey, grey, 255]);
+        }
+    }
+
+    if count == 0 {
+        return Comparison::Match;
+    }
+
+    Comparison::Mismatch(Mismatch {
+        count,
+        max_difference,
+        bounds: (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1),
+        diff,
+    })
+}
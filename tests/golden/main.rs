@@ -0,0 +1,166 @@
+//! Golden image regression tests for the renderer.
+//!
+//! Every scene in `scenes` is rendered headlessly and compared against a
+//! reference PNG in `tests/golden/references`. `cargo test --test golden`
+//! checks them, with `GOLDEN_BLESS=1` set it (re)writes the references after
+//! an intended rendering change.
+//!
+//! Rendering needs an OSMesa headless context. Where there is none the test
+//! fails, unless `GOLDEN_ALLOW_SKIP=1` is set to skip the scenes instead.
+
+extern crate flapply_bird;
+
+mod diff;
+mod scenes;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use flapply_bird::gfx::image::RgbaImage;
+use flapply_bird::gfx::{Camera, HeadlessError, HeadlessGraphics, CANVAS_HEIGHT, CANVAS_WIDTH};
+
+use diff::{compare, Comparison};
+use scenes::{Scene, SCENES};
+
+const REFERENCE_DIR: &str = "tests/golden/references";
+/// Where rendered images and diffs of failing scenes are written
+const OUTPUT_DIR: &str = "target/golden";
+
+/// Per channel difference still counted as a match
+const TOLERANCE: u8 = 2;
+
+#[test]
+fn golden_scenes() {
+    let mut headless = match HeadlessGraphics::new(CANVAS_WIDTH, CANVAS_HEIGHT) {
+        Ok(headless) => headless,
+        Err(HeadlessError::Creation(ref e)) if env::var_os("GOLDEN_ALLOW_SKIP").is_some() => {
+            println!("Skipping golden scenes, no headless context: {}", e);
+            return;
+        }
+        Err(e) => panic!(
+            "{}, set GOLDEN_ALLOW_SKIP=1 to skip the golden scenes without a headless context",
+            e
+        ),
+    };
+
+    let bless = env::var_os("GOLDEN_BLESS").is_some();
+    assert!(run(&mut headless, bless), "Golden scenes failed");
+}
+
+/// Renders every scene with `headless`, which has to be `CANVAS_WIDTH` by
+/// `CANVAS_HEIGHT`, and checks it against its reference, or overwrites the
+/// reference if `bless` is set. Returns whether all scenes passed.
+fn run(headless: &mut HeadlessGraphics, bless: bool) -> bool {
+    let mut failed = 0;
+    for scene in SCENES {
+        let live_before = headless.graphics().live_gl_objects();
+        let actual = render(headless, scene);
+        let live_after = headless.graphics().live_gl_objects();
+
+        let passed = match check(scene.name, &actual, bless) {
+            Ok(passed) => passed,
+            Err(e) => {
+                println!("{}: {}", scene.name, e);
+                false
+            }
+        };
+
+        // Everything a scene creates is dropped by the time it returns
+        let leaked = live_after != live_before;
+        if leaked {
+            println!(
+                "{}: leaked GL objects, {:?} alive before and {:?} after",
+                scene.name, live_before, live_after
+            );
+        }
+
+        if !passed || leaked {
+            failed += 1;
+        }
+    }
+
+    println!(
+        "{} of {} golden scenes passed",
+        SCENES.len() - failed,
+        SCENES.len()
+    );
+    failed == 0
+}
+
+fn render(headless: &mut HeadlessGraphics, scene: &Scene) -> RgbaImage {
+    let (w, h) = (headless.width() as f32, headless.height() as f32);
+    {
+        let gfx = headless.graphics();
+        gfx.set_render_target(None);
+        gfx.clear();
+        gfx.set_camera(&Camera::new(w, h));
+        (scene.draw)(gfx);
+    }
+    headless.finish();
+
+    headless.graphics().read_pixels(false)
+}
+
+/// Compares `actual` against the reference of scene `name` and reports the
+/// outcome. Returns whether they match.
+fn check(name: &str, actual: &RgbaImage, bless: bool) -> io::Result<bool> {
+    let reference_path = Path::new(REFERENCE_DIR).join(format!("{}.png", name));
+
+    if bless {
+        fs::create_dir_all(REFERENCE_DIR)?;
+        actual.save_png(&reference_path)?;
+        println!("{}: wrote {}", name, reference_path.display());
+        return Ok(true);
+    }
+
+    let expected = RgbaImage::load(&reference_path).map_err(|e| {
+        let e = io::Error::from(e);
+        io::Error::new(e.kind(), format!("{} (set GOLDEN_BLESS to create it)", e))
+    })?;
+
+    fs::create_dir_all(OUTPUT_DIR)?;
+    let actual_path = Path::new(OUTPUT_DIR).join(format!("{}.actual.png", name));
+    let diff_path = Path::new(OUTPUT_DIR).join(format!("{}.diff.png", name));
+
+    match compare(&expected, actual, TOLERANCE) {
+        Comparison::Match => {
+            println!("{}: ok", name);
+            Ok(true)
+        }
+        Comparison::SizeMismatch {
+            expected,
+            actual: size,
+        } => {
+            actual.save_png(&actual_path)?;
+            println!(
+                "{}: rendered {}x{} but the reference is {}x{}, see {}",
+                name,
+                size.0,
+                size.1,
+                expected.0,
+                expected.1,
+                actual_path.display()
+            );
+            Ok(false)
+        }
+        Comparison::Mismatch(mismatch) => {
+            actual.save_png(&actual_path)?;
+            mismatch.diff.save_png(&diff_path)?;
+            let (x, y, w, h) = mismatch.bounds;
+            println!(
+                "{}: {} pixels differ by up to {} within {}x{} at ({}, {}), see {}",
+                name,
+                mismatch.count,
+                mismatch.max_difference,
+                w,
+                h,
+                x,
+                y,
+                diff_path.display()
+            );
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use flapply_bird::gfx::atlas::TextureRegion;
+use flapply_bird::gfx::font::{FontDescriptor, Glyph};
+use flapply_bird::gfx::image::{Image, Rgb8, RgbaImage};
+use flapply_bird::gfx::{
+    Align, BitmapFont, Camera, Color, Graphics, Sprite, Text, CANVAS_HEIGHT, CANVAS_WIDTH,
+};
+use flapply_bird::math::{Rect, Trans2};
+
+/// Scripted drawing whose result is compared against `<name>.png`
+pub struct Scene {
+    pub name: &'static str,
+    pub draw: fn(&mut Graphics),
+}
+
+pub const SCENES: &[Scene] = &[
+    Scene {
+        name: "texture_orientation",
+        draw: texture_orientation,
+    },
+    Scene {
+        name: "sprite_transforms",
+        draw: sprite_transforms,
+    },
+    Scene {
+        name: "texture_regions",
+        draw: texture_regions,
+    },
+    Scene {
+        name: "alpha_blending",
+        draw: alpha_blending,
+    },
+    Scene {
+        name: "batching",
+        draw: batching,
+    },
+    Scene {
+        name: "camera_offset",
+        draw: camera_offset,
+    },
+    Scene {
+        name: "render_target",
+        draw: render_target,
+    },
+    Scene {
+        name: "render_target_mid_frame",
+        draw: render_target_mid_frame,
+    },
+    Scene {
+        name: "bitmap_text",
+        draw: bitmap_text,
+    },
+    Scene {
+        name: "rgb_image",
+        draw: rgb_image,
+    },
+];
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+/// 8x8 image with a differently colored quadrant in each corner, so any flip
+/// or rotation shows up: red top left, green top right, blue bottom left and
+/// white bottom right.
+fn marker() -> RgbaImage {
+    let mut image = RgbaImage::new(8, 8);
+    for (i, pixel) in image.bytes_mut().chunks_mut(4).enumerate() {
+        let (x, y) = (i % 8, i / 8);
+        let color = match (x < 4, y < 4) {
+            (true, true) => RED,
+            (false, true) => GREEN,
+            (true, false) => BLUE,
+            (false, false) => WHITE,
+        };
+        pixel.copy_from_slice(&color);
+    }
+    image
+}
+
+/// 32x32 color ramp going from transparent on the left to opaque on the right
+fn gradient() -> RgbaImage {
+    let mut image = RgbaImage::new(32, 32);
+    for (i, pixel) in image.bytes_mut().chunks_mut(4).enumerate() {
+        let (x, y) = ((i % 32) as u8, (i / 32) as u8);
+        pixel.copy_from_slice(&[x * 8, y * 8, 255 - y * 8, x * 8 + 7]);
+    }
+    image
+}
+
+/// `w`x`h` checkerboard with `cell` pixel squares
+fn checkerboard(w: u32, h: u32, cell: u32) -> RgbaImage {
+    let mut image = RgbaImage::new(w, h);
+    for (i, pixel) in image.bytes_mut().chunks_mut(4).enumerate() {
+        let (x, y) = (i as u32 % w, i as u32 / w);
+        let color = if (x / cell + y / cell) % 2 == 1 {
+            [90, 90, 90, 255]
+        } else {
+            [40, 40, 40, 255]
+        };
+        pixel.copy_from_slice(&color);
+    }
+    image
+}
+
+/// Opaque background so blending has something to blend with
+fn draw_background(gfx: &mut Graphics) {
+    let background = gfx.create_texture(&checkerboard(CANVAS_WIDTH, CANVAS_HEIGHT, 16));
+    gfx.draw_texture(&background);
+}
+
+fn texture_orientation(gfx: &mut Graphics) {
+    let marker = gfx.create_texture(&marker());
+
+    // Natural size in the bottom left corner, then magnified with nearest filtering
+    gfx.draw_texture(&marker);
+    gfx.draw_sprite(
+        &marker,
+        &Sprite::for_texture(&marker)
+            .at(16.0, 16.0)
+            .with_scale(8.0, 8.0),
+    );
+    gfx.draw_sprite(
+        &marker,
+        &Sprite::from_rect(&Rect::new(100.0, 300.0, 150.0, 90.0)),
+    );
+}
+
+fn sprite_transforms(gfx: &mut Graphics) {
+    draw_background(gfx);
+    let marker = gfx.create_texture(&marker());
+    let sprite = Sprite::for_texture(&marker)
+        .with_anchor(0.5, 0.5)
+        .with_scale(6.0, 6.0);
+
+    for (i, rotation) in [0.0, PI / 6.0, PI / 4.0, PI / 2.0, PI].iter().enumerate() {
+        let y = 470.0 - i as f32 * 90.0;
+        gfx.draw_sprite(&marker, &sprite.at(50.0, y).with_rotation(*rotation));
+        gfx.draw_sprite(
+            &marker,
+            &sprite.at(144.0, y).with_rotation(*rotation).flip_x(),
+        );
+        gfx.draw_sprite(
+            &marker,
+            &sprite.at(238.0, y).with_rotation(*rotation).flip_y(),
+        );
+    }
+}
+
+fn texture_regions(gfx: &mut Graphics) {
+    draw_background(gfx);
+    let marker = gfx.create_texture(&marker());
+
+    // Each quadrant on its own, placed where it is in the image
+    let quadrants = [
+        (TextureRegion::new(0, 0, 4, 4), 40.0, 300.0),
+        (TextureRegion::new(4, 0, 4, 4), 160.0, 300.0),
+        (TextureRegion::new(0, 4, 4, 4), 40.0, 180.0),
+        (TextureRegion::new(4, 4, 4, 4), 160.0, 180.0),
+    ];
+    for &(ref region, x, y) in quadrants.iter() {
+        gfx.draw_sprite_region(
+            &marker,
+            region,
+            &Sprite::for_region(region).at(x, y).with_scale(22.0, 22.0),
+        );
+    }
+
+    // Region straddling all four quadrants
+    gfx.draw_texture_region(
+        &marker,
+        &TextureRegion::new(2, 2, 4, 4),
+        Trans2::translate(104.0, 20.0) * Trans2::scale(80.0, 80.0),
+    );
+}
+
+fn alpha_blending(gfx: &mut Graphics) {
+    draw_background(gfx);
+    let gradient = gfx.create_texture(&gradient());
+    let sprite = Sprite::for_texture(&gradient).with_scale(4.0, 4.0);
+
+    gfx.draw_sprite(&gradient, &sprite.at(16.0, 360.0));
+    gfx.draw_sprite(
+        &gradient,
+        &sprite.at(80.0, 320.0).with_tint(Color::rgb(1.0, 0.5, 0.25)),
+    );
+    gfx.draw_sprite(
+        &gradient,
+        &sprite
+            .at(144.0, 280.0)
+            .with_tint(Color::white().with_alpha(0.5)),
+    );
+
+    // Overlapping translucent tints
+    let white = gfx.create_texture(&RgbaImage::from_components(1, 1, &WHITE));
+    let colors = [
+        Color::rgba(1.0, 0.0, 0.0, 0.5),
+        Color::rgba(0.0, 1.0, 0.0, 0.5),
+        Color::rgba(0.0, 0.0, 1.0, 0.5),
+    ];
+    for (i, color) in colors.iter().enumerate() {
+        let offset = i as f32 * 40.0;
+        gfx.draw_sprite(
+            &white,
+            &Sprite::new(120.0, 120.0)
+                .at(30.0 + offset, 30.0 + offset)
+                .with_tint(*color),
+        );
+    }
+}
+
+fn batching(gfx: &mut Graphics) {
+    let marker = gfx.create_texture(&marker());
+    let gradient = gfx.create_texture(&gradient());
+
+    // More quads than the initial batch capacity, alternating textures in
+    // runs so batches both break and grow
+    for i in 0..288 {
+        let (col, row) = (i % 18, i / 18);
+        let texture = if (i / 7) % 2 == 0 { &marker } else { &gradient };
+        gfx.draw_sprite(
+            texture,
+            &Sprite::new(14.0, 14.0).at(col as f32 * 16.0 + 1.0, row as f32 * 32.0 + 1.0),
+        );
+    }
+}
+
+fn camera_offset(gfx: &mut Graphics) {
+    draw_background(gfx);
+    let marker = gfx.create_texture(&marker());
+
+    // Half size camera looking at a shifted part of the world doubles everything
+    gfx.set_camera(
+        &Camera::new(CANVAS_WIDTH as f32 / 2.0, CANVAS_HEIGHT as f32 / 2.0)
+            .with_offset(-20.0, 10.0),
+    );
+    gfx.draw_sprite(&marker, &Sprite::for_texture(&marker).at(0.0, 10.0));
+    gfx.draw_sprite(
+        &marker,
+        &Sprite::for_texture(&marker)
+            .at(60.0, 120.0)
+            .with_scale(4.0, 4.0)
+            .with_rotation(PI / 3.0),
+    );
+}
+
+fn render_target(gfx: &mut Graphics) {
+    let marker = gfx.create_texture(&marker());
+    let target = gfx.create_render_target(32, 32);
+
+    gfx.set_render_target(Some(&target));
+    gfx.clear();
+    gfx.set_camera(&Camera::new(32.0, 32.0));
+    gfx.draw_sprite(
+        &marker,
+        &Sprite::for_texture(&marker)
+            .at(4.0, 4.0)
+            .with_scale(2.0, 2.0),
+    );
+    gfx.draw_sprite(
+        &marker,
+        &Sprite::for_texture(&marker)
+            .at(24.0, 24.0)
+            .with_anchor(0.5, 0.5)
+            .with_rotation(PI / 2.0),
+    );
+
+    gfx.set_render_target(None);
+    gfx.set_camera(&Camera::new(CANVAS_WIDTH as f32, CANVAS_HEIGHT as f32));
+    draw_background(gfx);
+    gfx.draw_sprite(
+        target.texture(),
+        &Sprite::for_texture(target.texture())
+            .at(16.0, 128.0)
+            .with_scale(8.0, 8.0),
+    );
+}
+
+/// Creates targets while draws are queued, both for the screen and for
+/// another target, which must not lose those draws or redirect later ones.
+fn render_target_mid_frame(gfx: &mut Graphics) {
+    let marker = gfx.create_texture(&marker());
+
+    draw_background(gfx);
+    gfx.draw_sprite(
+        &marker,
+        &Sprite::for_texture(&marker)
+            .at(16.0, 16.0)
+            .with_scale(4.0, 4.0),
+    );
+    let outer = gfx.create_render_target(32, 32);
+    gfx.draw_sprite(
+        &marker,
+        &Sprite::for_texture(&marker)
+            .at(64.0, 16.0)
+            .with_scale(4.0, 4.0),
+    );
+
+    gfx.set_render_target(Some(&outer));
+    gfx.clear();
+    gfx.set_camera(&Camera::new(32.0, 32.0));
+    gfx.draw_texture(&marker);
+    let inner = gfx.create_render_target(16, 16);
+    gfx.draw_sprite(&marker, &Sprite::for_texture(&marker).at(24.0, 24.0));
+
+    gfx.set_render_target(Some(&inner));
+    gfx.clear();
+    gfx.set_camera(&Camera::new(16.0, 16.0));
+    gfx.draw_sprite(&marker, &Sprite::for_texture(&marker).with_scale(2.0, 2.0));
+
+    gfx.set_render_target(None);
+    gfx.set_camera(&Camera::new(CANVAS_WIDTH as f32, CANVAS_HEIGHT as f32));
+    gfx.draw_sprite(
+        outer.texture(),
+        &Sprite::for_texture(outer.texture())
+            .at(16.0, 128.0)
+            .with_scale(4.0, 4.0),
+    );
+    gfx.draw_sprite(
+        inner.texture(),
+        &Sprite::for_texture(inner.texture())
+            .at(160.0, 128.0)
+            .with_scale(4.0, 4.0),
+    );
+}
+
+/// 3x5 pixel digits, one row per line from the top
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Font with the digits of `DIGITS` side by side in a white texture
+fn digit_font(gfx: &mut Graphics) -> BitmapFont {
+    let mut image = RgbaImage::new(30, 5);
+    let mut glyphs = HashMap::new();
+    for (i, rows) in DIGITS.iter().enumerate() {
+        for (y, row) in rows.iter().enumerate() {
+            for x in 0..3 {
+                if row & (0b100 >> x) != 0 {
+                    let offset = (y * 30 + i * 3 + x) * 4;
+                    image.bytes_mut()[offset..offset + 4].copy_from_slice(&WHITE);
+                }
+            }
+        }
+
+        let glyph = Glyph {
+            region: TextureRegion::new(i as u32 * 3, 0, 3, 5),
+            offset_x: 0,
+            offset_y: 1,
+            advance: 4,
+        };
+        glyphs.insert((b'0' + i as u8) as char, glyph);
+    }
+
+    let mut kernings = HashMap::new();
+    kernings.insert(('1', '1'), -1);
+
+    let descriptor = FontDescriptor {
+        line_height: 7,
+        base: 6,
+        page: String::new(),
+        glyphs,
+        kernings,
+    };
+    BitmapFont::new(gfx.create_texture(&image), descriptor).unwrap()
+}
+
+fn bitmap_text(gfx: &mut Graphics) {
+    draw_background(gfx);
+    let font = digit_font(gfx);
+
+    gfx.draw_text(
+        &font,
+        &Text::new("0123456789").at(8.0, 480.0).with_scale(3.0),
+    );
+    gfx.draw_text(
+        &font,
+        &Text::new("42")
+            .at(144.0, 380.0)
+            .with_align(Align::Center)
+            .with_scale(8.0)
+            .with_color(Color::rgb(1.0, 0.8, 0.2)),
+    );
+    // Kerned pairs and a second line
+    gfx.draw_text(
+        &font,
+        &Text::new("1112\n7")
+            .at(280.0, 250.0)
+            .with_align(Align::Right)
+            .with_scale(5.0)
+            .with_color(Color::white().with_alpha(0.6)),
+    );
+}
+
+fn rgb_image(gfx: &mut Graphics) {
+    draw_background(gfx);
+
+    // `marker` without alpha, uploaded as a 3 channel texture
+    let mut marker = Image::from_pixel(8, 8, Rgb8::new(255, 255, 255));
+    for (y, row) in marker.rows_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            match (x < 4, y < 4) {
+                (true, true) => *pixel = Rgb8::new(255, 0, 0),
+                (false, true) => *pixel = Rgb8::new(0, 255, 0),
+                (true, false) => *pixel = Rgb8::new(0, 0, 255),
+                (false, false) => (),
+            }
+        }
+    }
+    let marker = gfx.create_texture_from_image(&marker.into()).unwrap();
+    gfx.draw_sprite(
+        &marker,
+        &Sprite::for_texture(&marker)
+            .at(16.0, 300.0)
+            .with_scale(16.0, 16.0),
+    );
+
+    // Rows of 15 bytes, padded to 16 on upload
+    let mut diagonal = Image::<Rgb8>::new(5, 3);
+    for i in 0..3 {
+        diagonal.put_pixel(i + 1, i, Rgb8::new(255, 200, 0));
+    }
+    if let Some(pixel) = diagonal.pixel_mut(0, 2) {
+        *pixel = Rgb8::new(0, 200, 255);
+    }
+    let diagonal = gfx.create_texture_from_image(&diagonal.into()).unwrap();
+    gfx.draw_sprite(
+        &diagonal,
+        &Sprite::for_texture(&diagonal)
+            .at(24.0, 100.0)
+            .with_scale(48.0, 48.0),
+    );
+}